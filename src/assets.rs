@@ -2,6 +2,13 @@ use anyhow::anyhow;
 use gpui::{AssetSource, Result, SharedString};
 use rust_embed::RustEmbed;
 use std::borrow::Cow;
+use std::fs;
+use std::path::Component;
+
+use crate::config::USER_ICONS_PATH;
+
+/// assets below this prefix are read from `~/.git-launcher/icons` at runtime
+pub const USER_ICON_PREFIX: &str = "user-icons/";
 
 #[derive(RustEmbed)]
 #[folder = "$CARGO_MANIFEST_DIR/assets"]
@@ -15,13 +22,32 @@ impl AssetSource for Assets {
             return Ok(None);
         }
 
+        if let Some(file) = path.strip_prefix(USER_ICON_PREFIX) {
+            let file = std::path::Path::new(file);
+            if file
+                .components()
+                .any(|component| !matches!(component, Component::Normal(_)))
+            {
+                return Err(anyhow!("invalid user icon path \"{path}\""));
+            }
+            return Ok(Some(Cow::Owned(fs::read(USER_ICONS_PATH.join(file))?)));
+        }
+
         Self::get(path)
             .map(|f| Some(f.data))
             .ok_or_else(|| anyhow!("could not find asset at path \"{path}\""))
     }
 
     fn list(&self, path: &str) -> Result<Vec<SharedString>> {
+        let user_icons = fs::read_dir(&*USER_ICONS_PATH)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| format!("{USER_ICON_PREFIX}{}", entry.file_name().to_string_lossy()));
+
         Ok(Self::iter()
+            .map(|p| p.to_string())
+            .chain(user_icons)
             .filter_map(|p| p.starts_with(path).then(|| p.into()))
             .collect())
     }
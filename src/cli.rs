@@ -0,0 +1,561 @@
+use std::{io::Write, path::Path};
+
+use crate::{
+    GLOBAL_RUNTIME,
+    config::{self, Config, GitLauncherAnalysisConfig},
+    repo::{
+        self, Catalog, CatalogFormat, DiskUsage, History, HistoryEntry, LanguageAnalyzer, PathMap,
+        Query, Repo, RepoState, SortKey, StatsFormat, Trigger, TrustedActions, format_age,
+        format_date, format_size, now_secs, search_repos, sparkline,
+    },
+    system::{ActionRunner, FileOpener},
+};
+
+const USAGE: &str = "usage: git_launcher [command]
+
+commands:
+  open <query>              open the best match for <query>
+  recent [count]            list recently opened repos
+  stats                     show usage stats from the open history
+  rename <repo> [name]      set the display name, or clear it without a name
+  alias <repo> <alias>...   add search aliases
+  unalias <repo> <alias>... remove search aliases
+  archive <repo>            hide a repo from default results
+  unarchive <repo>          show an archived repo again
+  archived                  list archived repos
+  export [--format f] [file]
+                            write display names, aliases, archive flags and
+                            open counts of all repos as json, toml or csv, to
+                            stdout without a file
+  import [--format f] [--map old=new]... <file>
+                            merge an exported catalog, paths are rewritten
+                            by the --map prefixes and repos are matched by
+                            remote url when the paths differ
+  reanalyze [--full] <repo> detect the repo's language again, ignoring the cache,
+                            --full also ignores the analysis limits
+  code-stats [--format f] [--full] <repo> [file]
+                            lines per language, largest files and languages
+                            per top-level directory as markdown or json
+  activity [repo]           commits in the last 7, 30 and 365 days of every
+                            repo, or the history and top contributors of one
+  du [--refresh] [key]      disk usage per repo and in total, sorted by
+                            size, worktree, git or artifacts
+  run <repo> [action]       run an action from the repo's .git-launcher.toml,
+                            lists the actions without a name
+  help                      show this message
+
+<repo> is a repo path or any of its names
+
+without a command the launcher window is started";
+
+/// run a cli command, `None` means no command was given and the gui should start
+pub fn run(args: &[String]) -> Option<anyhow::Result<()>> {
+    let command = args.first()?;
+    let rest = &args[1..];
+
+    let result = match command.as_str() {
+        "open" => open(rest),
+        "recent" => recent(rest),
+        "stats" => stats(),
+        "rename" => rename(rest),
+        "alias" => alias(rest, true),
+        "unalias" => alias(rest, false),
+        "archive" => archive(rest, true),
+        "unarchive" => archive(rest, false),
+        "archived" => archived(),
+        "export" => export(rest),
+        "import" => import(rest),
+        "reanalyze" => reanalyze(rest),
+        "code-stats" => code_stats(rest),
+        "activity" => activity(rest),
+        "du" => disk_usage(rest),
+        "run" => run_action(rest),
+        "help" | "--help" | "-h" => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        _ => return None,
+    };
+
+    Some(result)
+}
+
+fn load_state() -> anyhow::Result<(Config, RepoState)> {
+    let config: Config = config::load()?;
+    repo::init_storage(&config.storage_config)?;
+    Ok((config, RepoState::load()?))
+}
+
+fn find_repo(state: &RepoState, ident: &str) -> anyhow::Result<Repo> {
+    state
+        .find(ident)
+        .ok_or_else(|| anyhow::anyhow!("no repo named \"{ident}\""))
+}
+
+fn open(args: &[String]) -> anyhow::Result<()> {
+    let (config, state) = load_state()?;
+    let query = Query::parse(&args.join(" "));
+    let Some(repo) = search_repos(&state.repos.read().unwrap(), &query, &config.ranking_config)
+        .into_iter()
+        .next()
+    else {
+        anyhow::bail!("no repo matches \"{}\"", args.join(" "));
+    };
+
+    if repo.unavailable {
+        anyhow::bail!("{} is on a volume that is not mounted", repo.path);
+    }
+
+    let editor = repo.editor(&config.editor_config).to_string();
+    let paths = repo.open_paths(config.editor_config.member_target);
+    let paths: Vec<&str> = paths.iter().map(String::as_str).collect();
+    FileOpener::open_all_with(&editor, &paths)?;
+    println!("{}", repo.path);
+
+    History::new(config.history_config).append(&HistoryEntry {
+        timestamp: now_secs(),
+        name: repo.name.clone(),
+        path: repo.path.clone(),
+        editor,
+        trigger: Trigger::Cli,
+    })?;
+
+    state.update(&repo.path, |repo| {
+        repo.record_open(now_secs(), config.ranking_config.max_samples)
+    })
+}
+
+fn rename(args: &[String]) -> anyhow::Result<()> {
+    let Some(ident) = args.first() else {
+        anyhow::bail!("usage: git_launcher rename <repo> [name]");
+    };
+    let (_, state) = load_state()?;
+    let repo = find_repo(&state, ident)?;
+
+    let name = args[1..].join(" ");
+    state.update(&repo.path, |repo| {
+        repo.display_name = (!name.is_empty()).then_some(name);
+    })
+}
+
+fn alias(args: &[String], add: bool) -> anyhow::Result<()> {
+    if args.len() < 2 {
+        anyhow::bail!("usage: git_launcher alias|unalias <repo> <alias>...");
+    }
+    let (_, state) = load_state()?;
+    let repo = find_repo(&state, &args[0])?;
+
+    state.update(&repo.path, |repo| {
+        for alias in &args[1..] {
+            if add {
+                if !repo.aliases.contains(alias) {
+                    repo.aliases.push(alias.clone());
+                }
+            } else {
+                repo.aliases.retain(|existing| existing != alias);
+            }
+        }
+    })
+}
+
+fn archive(args: &[String], archived: bool) -> anyhow::Result<()> {
+    let Some(ident) = args.first() else {
+        anyhow::bail!("usage: git_launcher archive|unarchive <repo>");
+    };
+    let (_, state) = load_state()?;
+    let repo = find_repo(&state, ident)?;
+
+    state.update(&repo.path, |repo| repo.archived = archived)
+}
+
+fn archived() -> anyhow::Result<()> {
+    let (_, state) = load_state()?;
+    let mut repos: Vec<Repo> = state
+        .repos
+        .read()
+        .unwrap()
+        .iter()
+        .filter(|repo| repo.archived)
+        .cloned()
+        .collect();
+    repos.sort_by(|a, b| a.path.cmp(&b.path));
+
+    for repo in repos {
+        println!("{:<30}  {}", repo.title(), repo.path);
+    }
+    Ok(())
+}
+
+/// `--format` flag, else the file extension, else json
+fn catalog_format(format: Option<&String>, file: Option<&String>) -> anyhow::Result<CatalogFormat> {
+    if let Some(format) = format {
+        return CatalogFormat::parse(format)
+            .ok_or_else(|| anyhow::anyhow!("unknown format {format}, use json, toml or csv"));
+    }
+    Ok(file
+        .and_then(|file| CatalogFormat::from_path(Path::new(file)))
+        .unwrap_or(CatalogFormat::Json))
+}
+
+fn export(args: &[String]) -> anyhow::Result<()> {
+    let mut format = None;
+    let mut file = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => format = args.next(),
+            _ => file = Some(arg),
+        }
+    }
+
+    let (_, state) = load_state()?;
+    let content = Catalog::from_state(&state).write(catalog_format(format, file)?)?;
+    match file {
+        Some(file) => std::fs::write(file, content)?,
+        None => print!("{content}"),
+    }
+    Ok(())
+}
+
+fn import(args: &[String]) -> anyhow::Result<()> {
+    let mut format = None;
+    let mut file = None;
+    let mut maps = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => format = args.next(),
+            "--map" => {
+                let Some(rule) = args.next() else {
+                    anyhow::bail!("--map needs an old=new prefix");
+                };
+                maps.push(PathMap::parse(rule)?);
+            }
+            _ => file = Some(arg),
+        }
+    }
+    let Some(file) = file else {
+        anyhow::bail!("usage: git_launcher import [--format f] [--map old=new]... <file>");
+    };
+
+    let content = std::fs::read_to_string(file)?;
+    let catalog = Catalog::read(&content, catalog_format(format, Some(file))?)?;
+
+    let (config, state) = load_state()?;
+    let report = state.import(catalog, &maps, config.ranking_config.max_samples)?;
+
+    println!(
+        "matched {} by path, {} by remote, added {}",
+        report.by_path, report.by_remote, report.added
+    );
+    if !report.skipped.is_empty() {
+        println!("\nnot found:");
+        for path in report.skipped {
+            println!("  {path}");
+        }
+    }
+    Ok(())
+}
+
+fn disk_usage(args: &[String]) -> anyhow::Result<()> {
+    let refresh = args.iter().any(|arg| arg == "--refresh");
+    let sort = match args.iter().find(|arg| !arg.starts_with("--")) {
+        Some(key) => {
+            SortKey::parse(key).ok_or_else(|| anyhow::anyhow!("unknown sort key {key}"))?
+        }
+        None => SortKey::Size,
+    };
+
+    let (config, state) = load_state()?;
+    GLOBAL_RUNTIME.block_on(repo::measure_disk_usage(
+        &state,
+        &config.repo_config.ignore_dirs,
+        config.repo_config.max_concurrent_tasks,
+        refresh,
+    ))?;
+
+    let repos = state.repos.read().unwrap();
+    let mut measured: Vec<&Repo> = repos
+        .iter()
+        .filter(|repo| repo.parent.is_none() && repo.disk_usage.is_some())
+        .collect();
+    measured.sort_by_key(|repo| std::cmp::Reverse(sort.bytes(repo)));
+
+    println!(
+        "{:>10}  {:>10}  {:>10}  {:>10}  path",
+        "total", "worktree", ".git", "artifacts"
+    );
+    let print = |usage: &DiskUsage, label: &str| {
+        println!(
+            "{:>10}  {:>10}  {:>10}  {:>10}  {}",
+            format_size(usage.total()),
+            format_size(usage.worktree),
+            format_size(usage.git),
+            format_size(usage.artifacts),
+            label
+        );
+    };
+    for repo in &measured {
+        print(repo.disk_usage.as_ref().unwrap(), &repo.path);
+    }
+    print(&DiskUsage::sum(measured.iter().copied()), "total");
+    Ok(())
+}
+
+fn reanalyze(args: &[String]) -> anyhow::Result<()> {
+    let full = args.iter().any(|arg| arg == "--full");
+    let Some(ident) = args.iter().find(|arg| !arg.starts_with("--")) else {
+        anyhow::bail!("usage: git_launcher reanalyze [--full] <repo>");
+    };
+    let (config, state) = load_state()?;
+    let repo = find_repo(&state, ident)?;
+    if repo.unavailable {
+        anyhow::bail!("{} is on a volume that is not mounted", repo.path);
+    }
+
+    let limits = analysis_limits(&config, full);
+    let analysis = repo::analyze(&repo.path, true, &limits)?;
+    for stat in &analysis.languages {
+        println!(
+            "{:<20}  {:>8} lines  {:>6} files",
+            stat.name, stat.code, stat.files
+        );
+    }
+    if analysis.partial {
+        println!("\ncounted from a sample, run with --full to count every file");
+    }
+    state.update(&repo.path, |repo| {
+        repo.set_analysis(analysis);
+        repo.analyzed_at = Some(now_secs());
+    })
+}
+
+/// the configured limits, or none with `--full`
+fn analysis_limits(config: &Config, full: bool) -> GitLauncherAnalysisConfig {
+    let limits = config.analysis_config;
+    if !full {
+        return limits;
+    }
+    GitLauncherAnalysisConfig {
+        max_files: 0,
+        max_bytes: 0,
+        timeout_secs: 0,
+        ..limits
+    }
+}
+
+fn code_stats(args: &[String]) -> anyhow::Result<()> {
+    let mut format = None;
+    let mut full = false;
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => format = args.next(),
+            "--full" => full = true,
+            _ => positional.push(arg),
+        }
+    }
+    let Some(ident) = positional.first() else {
+        anyhow::bail!("usage: git_launcher code-stats [--format f] [--full] <repo> [file]");
+    };
+    let file = positional.get(1).copied();
+
+    let format = match format {
+        Some(format) => StatsFormat::parse(format)
+            .ok_or_else(|| anyhow::anyhow!("unknown format {format}, use markdown or json"))?,
+        None => file
+            .and_then(|file| Path::new(file).extension()?.to_str())
+            .and_then(StatsFormat::parse)
+            .unwrap_or(StatsFormat::Markdown),
+    };
+
+    let (config, state) = load_state()?;
+    let repo = find_repo(&state, ident)?;
+    if repo.unavailable {
+        anyhow::bail!("{} is on a volume that is not mounted", repo.path);
+    }
+
+    let limits = analysis_limits(&config, full);
+    let stats = repo::with_priority(&limits, || {
+        LanguageAnalyzer::new(&repo.path)
+            .with_limits(&limits)
+            .code_stats()
+    })?;
+    let content = stats.write(format, repo.title())?;
+    match file {
+        Some(file) => std::fs::write(file, content)?,
+        None => print!("{content}"),
+    }
+    Ok(())
+}
+
+/// weeks in the activity sparkline
+const ACTIVITY_WEEKS: usize = 26;
+
+fn activity(args: &[String]) -> anyhow::Result<()> {
+    let (_, state) = load_state()?;
+    let now = now_secs();
+
+    if let Some(ident) = args.first() {
+        let repo = find_repo(&state, ident)?;
+        let Some(activity) = &repo.activity else {
+            anyhow::bail!("{} has no commits or was not analysed yet", repo.title());
+        };
+        println!(
+            "first commit: {}, last commit: {} ({} ago)",
+            format_date(activity.first_commit),
+            format_date(activity.last_commit),
+            format_age(now.saturating_sub(activity.last_commit))
+        );
+        println!(
+            "commits: {} in 7 days, {} in 30 days, {} in 365 days, {} in total",
+            activity.commits_within(now, 7),
+            activity.commits_within(now, 30),
+            activity.commits_within(now, 365),
+            activity.commits
+        );
+        println!(
+            "weekly: {}",
+            sparkline(&activity.weekly(now, ACTIVITY_WEEKS))
+        );
+        println!("\ntop contributors:");
+        for contributor in &activity.contributors {
+            println!("{:>6}  {}", contributor.commits, contributor.name);
+        }
+        return Ok(());
+    }
+
+    let repos = state.repos.read().unwrap();
+    let mut active: Vec<&Repo> = repos
+        .iter()
+        .filter(|repo| repo.activity.is_some())
+        .collect();
+    active.sort_by_key(|repo| {
+        std::cmp::Reverse(repo.activity.as_ref().map(|activity| activity.last_commit))
+    });
+
+    println!(
+        "{:>5}  {:>5}  {:>5}  {:>6}  {:<width$}  path",
+        "7d",
+        "30d",
+        "365d",
+        "last",
+        "weekly",
+        width = ACTIVITY_WEEKS
+    );
+    for repo in active {
+        let activity = repo.activity.as_ref().unwrap();
+        println!(
+            "{:>5}  {:>5}  {:>5}  {:>6}  {}  {}",
+            activity.commits_within(now, 7),
+            activity.commits_within(now, 30),
+            activity.commits_within(now, 365),
+            format_age(now.saturating_sub(activity.last_commit)),
+            sparkline(&activity.weekly(now, ACTIVITY_WEEKS)),
+            repo.path
+        );
+    }
+    Ok(())
+}
+
+fn run_action(args: &[String]) -> anyhow::Result<()> {
+    let Some(ident) = args.first() else {
+        anyhow::bail!("usage: git_launcher run <repo> [action]");
+    };
+    let (_, state) = load_state()?;
+    let repo = find_repo(&state, ident)?;
+
+    let name = args[1..].join(" ");
+    if name.is_empty() {
+        for action in repo.actions() {
+            println!("{:<20}  {}", action.name, action.command);
+        }
+        return Ok(());
+    }
+
+    let Some(action) = repo.actions().iter().find(|action| action.name == name) else {
+        anyhow::bail!("{} has no action \"{}\"", repo.title(), name);
+    };
+    if repo.unavailable {
+        anyhow::bail!("{} is on a volume that is not mounted", repo.path);
+    }
+
+    // the command comes from the repo, it runs once the user has seen it
+    let trusted = TrustedActions::new();
+    if !trusted.is_trusted(&repo.path, action) {
+        println!(
+            "{} wants to run this command from its .git-launcher.toml:\n\n    {}\n",
+            repo.title(),
+            action.command
+        );
+        print!("run it and trust it from now on? [y/N] ");
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            anyhow::bail!("action \"{}\" was not run", action.name);
+        }
+        trusted.trust(&repo.path, action)?;
+    }
+
+    let status = ActionRunner::run(&action.command, &repo.path)?.wait()?;
+    if !status.success() {
+        anyhow::bail!("action \"{}\" failed: {}", action.name, status);
+    }
+    Ok(())
+}
+
+fn recent(args: &[String]) -> anyhow::Result<()> {
+    let config: Config = config::load()?;
+    let count = match args.first() {
+        Some(count) => count.parse()?,
+        None => config.history_config.recent_count,
+    };
+
+    let now = now_secs();
+    for entry in History::new(config.history_config).recent(count) {
+        println!(
+            "{:>8}  {:<8}  {}",
+            format_age(now.saturating_sub(entry.timestamp)),
+            entry.trigger.as_str(),
+            entry.path
+        );
+    }
+    Ok(())
+}
+
+fn stats() -> anyhow::Result<()> {
+    let config: Config = config::load()?;
+    let stats = History::new(config.history_config).stats();
+
+    println!("total opens: {}", stats.total);
+    if let (Some(first), Some(last)) = (stats.first, stats.last) {
+        let now = now_secs();
+        println!(
+            "first open: {} ago, last open: {} ago",
+            format_age(now.saturating_sub(first)),
+            format_age(now.saturating_sub(last))
+        );
+    }
+
+    println!("\ntop repos:");
+    for (path, count) in stats.by_repo.iter().take(10) {
+        println!("{count:>6}  {path}");
+    }
+    println!("\neditors:");
+    for (editor, count) in &stats.by_editor {
+        println!("{count:>6}  {editor}");
+    }
+    println!("\ntriggers:");
+    for (trigger, count) in &stats.by_trigger {
+        println!("{count:>6}  {trigger}");
+    }
+    println!("\nopens by hour (utc):");
+    for (hour, count) in stats.by_hour.iter().enumerate() {
+        if *count > 0 {
+            println!("{hour:>6}  {count}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,287 @@
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::repo::{
+    Activity, AnalysisQueue, CodeStats, LanguageStat, Repo, StatsFormat, cached_code_stats,
+    code_stats, format_age, format_date, language_color, now_secs, sparkline,
+};
+use gpui::prelude::FluentBuilder;
+use gpui::*;
+use gpui_component::{ActiveTheme, StyledExt, h_flex, v_flex};
+
+pub const DETAILS_HEIGHT: f32 = 420.;
+const NUMBER_WIDTH: f32 = 64.;
+const ACTIVITY_WEEKS: usize = 52;
+/// moving through the results with the pane open only analyses the repo the
+/// selection stops on
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// code statistics of one repo, computed when the pane is opened
+pub struct CodeStatsPane {
+    title: String,
+    /// from the last background analysis
+    activity: Option<Activity>,
+    /// `None` while the analysis runs
+    stats: Option<Result<CodeStats, String>>,
+    /// nothing was cached and the analysis queue is paused
+    paused: bool,
+    /// dropped with the pane, so a repo that is no longer shown isn't analysed
+    _task: Task<()>,
+}
+
+impl CodeStatsPane {
+    pub fn new(repo: &Repo, cx: &mut Context<Self>) -> Self {
+        let path = repo.path.clone();
+        let limits = cx.global::<Config>().analysis_config;
+        let queue = cx.global::<AnalysisQueue>().clone();
+        let task = cx.spawn(async move |this, cx| {
+            cx.background_executor().timer(DEBOUNCE).await;
+
+            let cached = cx
+                .background_spawn({
+                    let path = path.clone();
+                    async move { cached_code_stats(&path) }
+                })
+                .await;
+            let stats = match cached {
+                Some(stats) => Ok(stats),
+                None => {
+                    if queue.is_paused() {
+                        let _ = this.update(cx, |this, cx| {
+                            this.paused = true;
+                            cx.notify();
+                        });
+                        queue.resumed().await;
+                    }
+                    cx.background_spawn(async move { code_stats(&path, &limits) })
+                        .await
+                }
+            };
+            let _ = this.update(cx, |this, cx| {
+                this.paused = false;
+                this.stats = Some(stats.map_err(|err| err.to_string()));
+                cx.notify();
+            });
+        });
+
+        Self {
+            title: repo.title().to_string(),
+            activity: repo.activity.clone(),
+            stats: None,
+            paused: false,
+            _task: task,
+        }
+    }
+
+    fn copy(&self, format: StatsFormat, cx: &mut App) {
+        let Some(Ok(stats)) = &self.stats else {
+            return;
+        };
+        match stats.write(format, &self.title) {
+            Ok(content) => cx.write_to_clipboard(ClipboardItem::new_string(content)),
+            Err(err) => eprintln!("failed to export code stats: {}", err),
+        }
+    }
+}
+
+fn header(columns: &[&'static str], cx: &App) -> Div {
+    let (first, numbers) = columns.split_first().unwrap();
+    h_flex()
+        .child(div().child(*first).flex_grow())
+        .children(
+            numbers
+                .iter()
+                .map(|column| div().child(*column).w(px(NUMBER_WIDTH)).text_right()),
+        )
+        .text_size(px(11.))
+        .text_color(cx.theme().muted_foreground)
+}
+
+fn row(label: String, numbers: [u32; 4], color: Option<u32>) -> Div {
+    h_flex()
+        .child(
+            h_flex()
+                .gap_2()
+                .when_some(color, |this, color| {
+                    this.child(div().size(px(8.)).rounded_full().bg(rgb(color)))
+                })
+                .child(div().child(label).truncate())
+                .flex_grow()
+                .overflow_hidden(),
+        )
+        .children(numbers.map(|number| {
+            div()
+                .child(number.to_string())
+                .w(px(NUMBER_WIDTH))
+                .text_right()
+        }))
+        .text_size(px(12.))
+}
+
+fn language_row(stat: &LanguageStat) -> Div {
+    row(
+        stat.name.clone(),
+        [stat.files, stat.code, stat.comments, stat.blanks],
+        Some(language_color(&stat.name)),
+    )
+}
+
+fn activity(activity: &Activity, cx: &App) -> Div {
+    let now = now_secs();
+    let line = |text: String| {
+        div()
+            .child(text)
+            .text_size(px(12.))
+            .text_color(cx.theme().muted_foreground)
+    };
+    v_flex()
+        .child(section("Activity", cx))
+        .child(
+            div()
+                .child(sparkline(&activity.weekly(now, ACTIVITY_WEEKS)))
+                .text_size(px(14.)),
+        )
+        .child(line(format!(
+            "{} commits in 7 days · {} in 30 days · {} in 365 days · {} in total",
+            activity.commits_within(now, 7),
+            activity.commits_within(now, 30),
+            activity.commits_within(now, 365),
+            activity.commits
+        )))
+        .child(line(format!(
+            "first commit {} · last commit {} ({} ago)",
+            format_date(activity.first_commit),
+            format_date(activity.last_commit),
+            format_age(now.saturating_sub(activity.last_commit))
+        )))
+        .child(line(
+            activity
+                .contributors
+                .iter()
+                .map(|contributor| format!("{} {}", contributor.name, contributor.commits))
+                .collect::<Vec<_>>()
+                .join(" · "),
+        ))
+}
+
+fn section(title: &'static str, cx: &App) -> Div {
+    div()
+        .child(title)
+        .mt_3()
+        .mb_1()
+        .text_size(px(13.))
+        .font_semibold()
+        .text_color(cx.theme().foreground)
+}
+
+impl Render for CodeStatsPane {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let action = |id: &'static str, label: &'static str, format: StatsFormat| {
+            div()
+                .id(id)
+                .child(label)
+                .text_size(px(11.))
+                .px_1()
+                .rounded_sm()
+                .border_1()
+                .border_color(cx.theme().border)
+                .text_color(cx.theme().muted_foreground)
+                .cursor_pointer()
+                .on_click(cx.listener(move |this, _, _, cx| this.copy(format, cx)))
+        };
+
+        let body = match &self.stats {
+            None => div()
+                .child(if self.paused {
+                    "analysis is paused"
+                } else {
+                    "analysing…"
+                })
+                .text_size(px(12.))
+                .text_color(cx.theme().muted_foreground),
+            Some(Err(err)) => div()
+                .child(err.clone())
+                .text_size(px(12.))
+                .text_color(cx.theme().danger),
+            Some(Ok(stats)) => v_flex()
+                .child(section("Languages", cx))
+                .child(header(
+                    &["Language", "Files", "Code", "Comments", "Blanks"],
+                    cx,
+                ))
+                .children(stats.languages.iter().map(language_row))
+                .child(language_row(&stats.total()).font_semibold())
+                .child(section("Largest files", cx))
+                .child(header(&["File", "Lines", "Code", "Comments", "Blanks"], cx))
+                .children(stats.largest_files.iter().map(|file| {
+                    row(
+                        file.path.clone(),
+                        [file.lines(), file.code, file.comments, file.blanks],
+                        Some(language_color(&file.language)),
+                    )
+                }))
+                .child(section("Directories", cx))
+                .child(header(
+                    &["Directory", "Files", "Code", "Comments", "Blanks"],
+                    cx,
+                ))
+                .children(stats.directories.iter().flat_map(|directory| {
+                    directory.languages.iter().map(|stat| {
+                        row(
+                            format!("{}  {}", directory.name, stat.name),
+                            [stat.files, stat.code, stat.comments, stat.blanks],
+                            Some(language_color(&stat.name)),
+                        )
+                    })
+                })),
+        };
+
+        v_flex()
+            .id("code-stats")
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(
+                        div()
+                            .child(self.title.clone())
+                            .text_size(px(16.))
+                            .flex_grow(),
+                    )
+                    .when(
+                        matches!(self.stats, Some(Ok(ref stats)) if stats.partial),
+                        |this| {
+                            this.child(
+                                div()
+                                    .child("sampled")
+                                    .text_size(px(11.))
+                                    .px_1()
+                                    .rounded_sm()
+                                    .border_1()
+                                    .border_color(cx.theme().border)
+                                    .text_color(cx.theme().muted_foreground),
+                            )
+                        },
+                    )
+                    .when(matches!(self.stats, Some(Ok(_))), |this| {
+                        this.child(action(
+                            "copy-markdown",
+                            "Copy Markdown",
+                            StatsFormat::Markdown,
+                        ))
+                        .child(action(
+                            "copy-json",
+                            "Copy JSON",
+                            StatsFormat::Json,
+                        ))
+                    }),
+            )
+            .when_some(self.activity.as_ref(), |this, activity_stats| {
+                this.child(activity(activity_stats, cx))
+            })
+            .child(body)
+            .px_4()
+            .py_2()
+            .h(px(DETAILS_HEIGHT))
+            .overflow_y_scroll()
+    }
+}
@@ -1,26 +1,76 @@
-use std::thread::spawn;
-
+use crate::component::code_stats::{CodeStatsPane, DETAILS_HEIGHT};
 use crate::component::repo_list::ITEM_HEIGHT;
 use crate::config::Config;
-use crate::repo::{LanguageAnalyzer, Repo, RepoState};
-use crate::system::FileOpener;
+use crate::repo::{
+    AnalysisQueue, DiskUsage, Facet, FacetKind, History, HistoryEntry, Query, Repo, RepoAction,
+    RepoState, SortKey, Trigger, TrustedActions, format_size, now_secs, search_repos,
+};
+use crate::system::{ActionRunner, FileOpener};
 use gpui::prelude::FluentBuilder;
 use gpui::*;
 use gpui_component::divider::Divider;
 use gpui_component::{
-    ActiveTheme, Icon, IconName, Sizable, StyledExt,
+    ActiveTheme, Icon, IconName, Sizable, StyledExt, h_flex,
     input::{InputEvent, InputState, TextInput},
     v_flex,
 };
 
+mod code_stats;
 mod repo_list;
 
 const MAX_ITEM_COUNT: usize = 6;
+const TOTALS_HEIGHT: f32 = 24.;
+const FACETS_HEIGHT: f32 = 28.;
+const CONFIRM_HEIGHT: f32 = 24.;
+
+/// what the result list shows, picked with a `:view` prefix in the search box
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum View {
+    Search,
+    /// `:recent`, the last opened repos
+    Recent,
+    /// `:archived`, hidden repos, opening one unarchives it
+    Archived,
+}
+
+impl View {
+    fn parse(search: &str) -> (Self, &str) {
+        let search = search.trim_start();
+        for (prefix, view) in [(":recent", View::Recent), (":archived", View::Archived)] {
+            if let Some(rest) = search.strip_prefix(prefix) {
+                return (view, rest.trim());
+            }
+        }
+        (View::Search, search)
+    }
+}
+
+/// index into the actions of the repo for the `1`-`9` key of cmd-1 to cmd-9,
+/// other keys held with cmd are shortcuts of their own
+fn action_index(key: &str) -> Option<usize> {
+    match key.parse::<usize>() {
+        Ok(digit @ 1..=9) if key.len() == 1 => Some(digit - 1),
+        _ => None,
+    }
+}
 
 pub struct GitLauncher {
     input: Entity<InputState>,
     result: Vec<Repo>,
     search: String,
+    view: View,
+    selected: usize,
+    /// disk usage summed over the results, shown for `sort:` queries
+    totals: Option<DiskUsage>,
+    /// languages, project types, hosts and tags of the results with their counts
+    facets: Vec<Facet>,
+    /// code statistics of the selected repo, toggled with cmd-i
+    details: Option<Entity<CodeStatsPane>>,
+    /// an action of the selected repo that isn't trusted yet, its command is
+    /// shown and pressing its shortcut again runs it
+    pending_action: Option<(usize, RepoAction)>,
+    /// kept so `:recent` reuses the list until the history file changes
+    history: History,
     _sub: Vec<Subscription>,
 }
 
@@ -41,36 +91,37 @@ impl GitLauncher {
     fn new(input: Entity<InputState>, window: &mut Window, cx: &mut Context<Self>) -> Self {
         let window_handle = window.window_handle().clone();
 
-        let _sub = vec![cx.subscribe(
+        let queue = cx.global::<AnalysisQueue>().clone();
+        let mut updated = queue.subscribe();
+        cx.spawn(async move |this, cx| {
+            while updated.changed().await.is_ok() {
+                let result = this.update(cx, |this, cx| {
+                    this.sync_results(cx);
+                    cx.notify();
+                });
+                if result.is_err() {
+                    break;
+                }
+            }
+        })
+        .detach();
+
+        let mut _sub = vec![cx.subscribe(
             &input,
             move |this, _, event: &InputEvent, ctx: &mut Context<Self>| match event {
                 InputEvent::Change => {
                     let text = this.input.read(ctx).value();
-                    let mut height = if text.len() > 0 {
-                        ITEM_HEIGHT * (this.result.len() + 1) as f32 + 60.
-                    } else {
-                        60.
-                    };
-
-                    if height > ITEM_HEIGHT * (MAX_ITEM_COUNT as f32) {
-                        height = ITEM_HEIGHT * (MAX_ITEM_COUNT as f32);
-                    }
+                    this.search = text.to_string().clone();
+                    this.refresh(ctx);
 
                     let _ = window_handle.update(ctx, |_, window: &mut Window, _| {
-                        window.resize(size(px(600.), px(height)));
+                        this.resize(window);
+                    });
+                }
+                InputEvent::PressEnter { .. } => {
+                    let _ = window_handle.update(ctx, |_, window: &mut Window, cx| {
+                        this.confirm(Trigger::Keyboard, window, cx);
                     });
-
-                    this.search = text.to_string().clone();
-
-                    let repos = ctx.read_global(|state: &RepoState, _: &App| state.repos.clone());
-
-                    let repo_state = repos.read().unwrap();
-
-                    this.result = repo_state
-                        .iter()
-                        .filter(|repo| repo.name.contains(&this.search))
-                        .cloned()
-                        .collect();
                 }
                 InputEvent::Blur => {
                     ctx.hide();
@@ -78,53 +129,311 @@ impl GitLauncher {
                 _ => {}
             },
         )];
+        // analysing in the background competes with typing, so it waits
+        // while the launcher is shown
+        _sub.push(cx.observe_window_activation(window, move |_, window, _| {
+            queue.set_paused(window.is_window_active());
+        }));
 
         Self {
             input,
             _sub,
             result: vec![],
             search: String::new(),
+            view: View::Search,
+            selected: 0,
+            totals: None,
+            facets: Vec::new(),
+            details: None,
+            pending_action: None,
+            history: History::new(cx.global::<Config>().history_config),
+        }
+    }
+
+    fn resize(&self, window: &mut Window) {
+        if self.details.is_some() {
+            window.resize(size(px(600.), px(DETAILS_HEIGHT + 60.)));
+            return;
+        }
+
+        let mut height = if self.search.len() > 0 {
+            let totals = if self.totals.is_some() {
+                TOTALS_HEIGHT
+            } else {
+                0.
+            };
+            let facets = if self.facets.is_empty() {
+                0.
+            } else {
+                FACETS_HEIGHT
+            };
+            let confirm = if self.pending_action.is_some() {
+                CONFIRM_HEIGHT
+            } else {
+                0.
+            };
+            ITEM_HEIGHT * (self.result.len() + 1) as f32 + 60. + totals + facets + confirm
+        } else {
+            60.
+        };
+
+        if height > ITEM_HEIGHT * (MAX_ITEM_COUNT as f32) {
+            height = ITEM_HEIGHT * (MAX_ITEM_COUNT as f32);
+        }
+
+        window.resize(size(px(600.), px(height)));
+    }
+
+    /// recompute `result` for the current search text
+    fn refresh(&mut self, cx: &mut App) {
+        let repos = cx.read_global(|state: &RepoState, _: &App| state.repos.clone());
+        let (ranking, history) =
+            cx.read_global(|state: &Config, _: &App| (state.ranking_config, state.history_config));
+
+        let repo_state = repos.read().unwrap();
+
+        let (view, search) = View::parse(&self.search);
+        let mut query = Query::parse(search);
+
+        self.view = view;
+        self.selected = 0;
+        self.details = None;
+        self.pending_action = None;
+        self.result = match view {
+            View::Recent => self
+                .history
+                .recent(history.recent_count)
+                .into_iter()
+                .filter_map(|entry| {
+                    repo_state
+                        .iter()
+                        .find(|repo| repo.path == entry.path)
+                        .cloned()
+                })
+                .filter(|repo| repo.fuzzy_score(&query.text).is_some())
+                .collect(),
+            View::Archived => {
+                query.archived = true;
+                search_repos(&repo_state, &query, &ranking)
+            }
+            View::Search => search_repos(&repo_state, &query, &ranking),
+        };
+        self.totals = (query.sort != SortKey::Frecency).then(|| DiskUsage::sum(&self.result));
+        self.facets = Facet::count(&self.result);
+    }
+
+    /// pick up changed repos without touching order or selection
+    fn sync_results(&mut self, cx: &mut App) {
+        let state = cx.global::<RepoState>().clone();
+        let repos = state.repos.read().unwrap();
+        for result in &mut self.result {
+            if let Some(repo) = repos.get(result) {
+                *result = repo.clone();
+            }
+        }
+    }
+
+    fn select(&mut self, delta: isize, window: &mut Window, cx: &mut Context<Self>) {
+        if self.result.is_empty() {
+            return;
+        }
+        let len = self.result.len() as isize;
+        self.selected = (self.selected as isize + delta).rem_euclid(len) as usize;
+        if self.details.is_some() {
+            self.show_details(cx);
+        }
+        if self.pending_action.take().is_some() {
+            self.resize(window);
+        }
+        cx.notify();
+    }
+
+    /// analyse the selected repo and show its code statistics instead of the results
+    fn show_details(&mut self, cx: &mut Context<Self>) {
+        self.details = self
+            .result
+            .get(self.selected)
+            .filter(|repo| !repo.unavailable)
+            .map(|repo| cx.new(|cx| CodeStatsPane::new(repo, cx)));
+    }
+
+    fn on_key_down(&mut self, event: &KeyDownEvent, window: &mut Window, cx: &mut Context<Self>) {
+        let keystroke = &event.keystroke;
+        match keystroke.key.as_str() {
+            "up" => self.select(-1, window, cx),
+            "down" => self.select(1, window, cx),
+            "r" if keystroke.modifiers.platform => {
+                let Some(repo) = self.result.get(self.selected) else {
+                    return;
+                };
+                if repo.unavailable {
+                    return;
+                }
+                let queue = cx.global::<AnalysisQueue>().clone();
+                queue.reanalyze(&repo.path);
+                // analysis waits while the launcher is shown
+                cx.hide();
+            }
+            "i" if keystroke.modifiers.platform => {
+                if self.details.is_some() {
+                    self.details = None;
+                } else {
+                    self.show_details(cx);
+                }
+                self.resize(window);
+                cx.notify();
+            }
+            key if keystroke.modifiers.platform && action_index(key).is_some() => {
+                let Some(index) = action_index(key) else {
+                    return;
+                };
+                let Some(repo) = self.result.get(self.selected).cloned() else {
+                    return;
+                };
+                if repo.unavailable {
+                    return;
+                }
+                let Some(action) = repo.actions().get(index).cloned() else {
+                    return;
+                };
+                // the command comes from the repo, the first press shows it
+                let trusted = TrustedActions::new();
+                if !trusted.is_trusted(&repo.path, &action) {
+                    let pending = Some((index, action.clone()));
+                    if self.pending_action != pending {
+                        self.pending_action = pending;
+                        self.resize(window);
+                        cx.notify();
+                        cx.stop_propagation();
+                        return;
+                    }
+                    if let Err(err) = trusted.trust(&repo.path, &action) {
+                        eprintln!("failed to trust action {}: {}", action.name, err);
+                        return;
+                    }
+                }
+                self.pending_action = None;
+                if let Err(err) = ActionRunner::run(&action.command, &repo.path) {
+                    eprintln!("failed to run action {}: {}", action.name, err);
+                }
+                cx.hide();
+            }
+            "escape" if self.details.is_some() => {
+                self.details = None;
+                self.resize(window);
+                cx.notify();
+            }
+            // plain cmd-backspace deletes to the start of the search input
+            "backspace" if keystroke.modifiers.platform && keystroke.modifiers.shift => {
+                let Some(repo) = self.result.get(self.selected).cloned() else {
+                    return;
+                };
+                self.set_archived(&repo, !repo.archived, window, cx);
+            }
+            _ => return,
         }
+        cx.stop_propagation();
+    }
+
+    /// open the selected repo, or unarchive it in the archived view
+    fn confirm(&mut self, trigger: Trigger, window: &mut Window, cx: &mut App) {
+        let Some(repo) = self.result.get(self.selected).cloned() else {
+            return;
+        };
+
+        if self.view == View::Archived {
+            self.set_archived(&repo, false, window, cx);
+            return;
+        }
+        if repo.unavailable {
+            return;
+        }
+
+        cx.hide();
+        Self::open_repo(repo, trigger, cx);
+        self.reset_search(window, cx);
+    }
+
+    fn set_archived(&mut self, repo: &Repo, archived: bool, window: &mut Window, cx: &mut App) {
+        let state = cx.global::<RepoState>().clone();
+        if let Err(err) = state.update(&repo.path, |repo| repo.archived = archived) {
+            eprintln!("failed to save repo state: {}", err);
+        }
+        self.refresh(cx);
+        self.resize(window);
     }
 
     fn click(
         self: &mut Self,
-        evt: &ClickEvent,
+        _: &ClickEvent,
         window: &mut Window,
         cx: &mut Context<Self>,
-        repo: Repo,
+        index: usize,
     ) {
-        cx.hide();
-        Self::open_repo(repo, cx);
-        Self::clear_search(self, evt, window, cx);
+        self.selected = index;
+        self.confirm(Trigger::Click, window, cx);
     }
 
-    fn open_repo(repo: Repo, cx: &mut Context<Self>) {
-        let editor = cx.read_global(|state: &Config, _: &App| state.editor_config.clone());
-        let _ = FileOpener::open_with(editor.editor.as_str(), repo.path.as_str()).unwrap();
-        let path = repo.path.clone();
-
-        let repos = cx.read_global(|state: &RepoState, _: &App| state.repos.clone());
-
-        spawn(move || {
-            let analyzer = LanguageAnalyzer::new(path.as_str());
-            let stats = analyzer.language().unwrap();
+    /// narrow the results to a facet, or widen them again when it is selected
+    fn toggle_facet(&mut self, facet: &Facet, window: &mut Window, cx: &mut Context<Self>) {
+        let search = facet.toggle(&self.search);
+        self.input.update(cx, |state, cx| {
+            state.set_value(search.clone(), window, cx);
+        });
+        self.search = search;
+        self.refresh(cx);
+        self.resize(window);
+        cx.notify();
+    }
 
-            let mut repo_state = repos.write().unwrap();
-            let mut repo = repo.clone();
+    fn open_repo(repo: Repo, trigger: Trigger, cx: &mut App) {
+        let (editor, ranking, history) = cx.read_global(|state: &Config, _: &App| {
+            (
+                state.editor_config.clone(),
+                state.ranking_config,
+                state.history_config,
+            )
+        });
+        let paths = repo.open_paths(editor.member_target);
+        let paths: Vec<&str> = paths.iter().map(String::as_str).collect();
+        let editor = repo.editor(&editor).to_string();
+        if let Err(err) = FileOpener::open_all_with(editor.as_str(), &paths) {
+            eprintln!("failed to open {} with {}: {}", repo.path, editor, err);
+            return;
+        }
 
-            repo.language = stats.0;
-            repo.count += 1;
+        let entry = HistoryEntry {
+            timestamp: now_secs(),
+            name: repo.name.clone(),
+            path: repo.path.clone(),
+            editor,
+            trigger,
+        };
+        if let Err(err) = History::new(history).append(&entry) {
+            eprintln!("failed to write history: {}", err);
+        }
 
-            repo_state.insert(repo);
-        })
-        .join()
-        .unwrap();
+        let result = cx.global::<RepoState>().update(&repo.path, |repo| {
+            repo.record_open(now_secs(), ranking.max_samples);
+        });
+        if let Err(err) = result {
+            eprintln!("failed to save repo state: {}", err);
+        }
+        cx.global::<AnalysisQueue>().push_front(&repo.path);
     }
 
     fn clear_search(self: &mut Self, _: &ClickEvent, window: &mut Window, cx: &mut Context<Self>) {
+        self.reset_search(window, cx);
+    }
+
+    fn reset_search(&mut self, window: &mut Window, cx: &mut App) {
         self.search = String::new();
         self.result = vec![];
+        self.selected = 0;
+        self.totals = None;
+        self.facets = Vec::new();
+        self.details = None;
+        self.pending_action = None;
 
         self.input.update(cx, |state, cx| {
             state.set_value(String::new(), window, cx);
@@ -137,6 +446,7 @@ impl Render for GitLauncher {
         div()
             .size_full()
             .v_flex()
+            .capture_key_down(cx.listener(Self::on_key_down))
             .child(
                 TextInput::new(&self.input)
                     .bordered(false)
@@ -164,19 +474,104 @@ impl Render for GitLauncher {
                         )
                     }),
             )
-            .when(self.search.len() > 0, |this| {
+            .when_some(self.details.clone(), |this, details| {
+                this.child(Divider::horizontal()).child(details)
+            })
+            .when(self.search.len() > 0 && self.details.is_none(), |this| {
+                let query = Query::parse(View::parse(&self.search).1);
                 this.child(Divider::horizontal())
+                    .when(!self.facets.is_empty(), |this| {
+                        this.child(
+                            h_flex()
+                                .id("facets")
+                                .gap_1()
+                                .px_4()
+                                .h(px(FACETS_HEIGHT))
+                                .items_center()
+                                .overflow_x_scroll()
+                                .children(self.facets.iter().enumerate().map(|(index, facet)| {
+                                    let selected = facet.is_selected(&query);
+                                    let label = match facet.kind {
+                                        FacetKind::Tag => {
+                                            format!("#{} {}", facet.label, facet.count)
+                                        }
+                                        _ => format!("{} {}", facet.label, facet.count),
+                                    };
+                                    let facet = facet.clone();
+                                    div()
+                                        .id(("facet", index))
+                                        .child(label)
+                                        .flex_shrink_0()
+                                        .text_size(px(11.))
+                                        .px_1()
+                                        .rounded_sm()
+                                        .border_1()
+                                        .border_color(cx.theme().border)
+                                        .cursor_pointer()
+                                        .map(|this| {
+                                            if selected {
+                                                this.bg(cx.theme().secondary)
+                                                    .text_color(cx.theme().secondary_foreground)
+                                            } else {
+                                                this.text_color(cx.theme().muted_foreground)
+                                            }
+                                        })
+                                        .on_click(cx.listener(move |this, _, window, cx| {
+                                            this.toggle_facet(&facet, window, cx);
+                                        }))
+                                })),
+                        )
+                    })
+                    .when_some(self.pending_action.as_ref(), |this, (index, action)| {
+                        this.child(
+                            div()
+                                .child(format!(
+                                    "⌘{} again to run `{}` from .git-launcher.toml",
+                                    index + 1,
+                                    action.command
+                                ))
+                                .h(px(CONFIRM_HEIGHT))
+                                .px_4()
+                                .pt_1()
+                                .truncate()
+                                .text_size(px(12.))
+                                .text_color(cx.theme().warning),
+                        )
+                    })
+                    .when_some(self.totals, |this, totals| {
+                        this.child(
+                            div()
+                                .child(format!(
+                                    "{} total · {} worktree · {} .git · {} build",
+                                    format_size(totals.total()),
+                                    format_size(totals.worktree),
+                                    format_size(totals.git),
+                                    format_size(totals.artifacts),
+                                ))
+                                .h(px(TOTALS_HEIGHT))
+                                .px_4()
+                                .pt_1()
+                                .text_size(px(12.))
+                                .text_color(cx.theme().muted_foreground),
+                        )
+                    })
                     .child(
                         v_flex()
-                            .children(self.result.iter().map(|repo| {
-                                let data = repo.clone();
+                            .children(self.result.iter().enumerate().map(|(index, repo)| {
                                 let id: SharedString = repo.path.clone().into();
+                                let selected = index == self.selected;
                                 div()
                                     .id(id)
                                     .on_click(cx.listener(move |item, evt, win, cx| {
-                                        Self::click(item, evt, win, cx, data.clone());
+                                        Self::click(item, evt, win, cx, index);
+                                    }))
+                                    .child(cx.new(|_| {
+                                        repo_list::RepoItem::new(
+                                            repo.clone(),
+                                            selected,
+                                            query.text.clone(),
+                                        )
                                     }))
-                                    .child(cx.new(|_| repo_list::RepoItem::new(repo.clone())))
                             }))
                             .mt_1()
                             .pb_1()
@@ -189,3 +584,18 @@ impl Render for GitLauncher {
             })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::action_index;
+
+    #[test]
+    fn only_digits_run_actions() {
+        assert_eq!(action_index("1"), Some(0));
+        assert_eq!(action_index("9"), Some(8));
+        // cmd-r, cmd-i and cmd-0 are not actions
+        for key in ["0", "r", "i", "+", "10", "backspace"] {
+            assert_eq!(action_index(key), None);
+        }
+    }
+}
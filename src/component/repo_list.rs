@@ -1,53 +1,220 @@
-use crate::repo::{Repo, get_language_icon};
+use crate::repo::{Repo, format_size, fuzzy_match, language_color, language_icon, now_secs};
+use gpui::prelude::FluentBuilder;
 use gpui::*;
 use gpui_component::{ActiveTheme, h_flex, v_flex};
 
-pub const ITEM_HEIGHT: f32 = 75.;
+pub const ITEM_HEIGHT: f32 = 80.;
+/// languages below this share are merged into `Other` in the language bar
+const MIN_BAR_SHARE: f64 = 0.02;
+/// weeks in the activity sparkline of a row
+const ACTIVITY_WEEKS: usize = 12;
 
 #[derive(Debug, Clone)]
 pub struct RepoItem {
     data: Repo,
+    selected: bool,
+    /// free text of the search, its matched characters are highlighted
+    text: String,
 }
 
 impl RepoItem {
-    pub fn new(data: Repo) -> Self {
-        Self { data }
+    pub fn new(data: Repo, selected: bool, text: String) -> Self {
+        Self {
+            data,
+            selected,
+            text,
+        }
+    }
+
+    /// `candidate` with the characters matched by the search highlighted
+    fn highlighted(&self, candidate: &str, cx: &App) -> Option<StyledText> {
+        let found = fuzzy_match(&self.text, candidate)?;
+        let style = HighlightStyle {
+            color: Some(cx.theme().primary),
+            font_weight: Some(FontWeight::BOLD),
+            ..Default::default()
+        };
+        let highlights = found
+            .ranges(candidate)
+            .into_iter()
+            .map(|range| (range, style));
+        Some(StyledText::new(candidate.to_string()).with_highlights(highlights))
     }
 }
 
 impl Render for RepoItem {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        let lang: SharedString = format!(
-            "languages/{}.png",
-            get_language_icon(self.data.language.clone().as_str())
-        )
-        .into();
+        let lang = language_icon(&self.data.language);
+        let bar = self.data.language_bar(MIN_BAR_SHARE);
+        // the path is only highlighted when it, not the title, matched
+        let title = self.data.title().to_string();
+        let title_match = self.highlighted(&title, cx);
+        let path_match = title_match
+            .is_none()
+            .then(|| self.highlighted(&self.data.path, cx))
+            .flatten();
 
         h_flex()
             .gap_3()
             .child(img(lang).size_6())
             .child(
                 v_flex()
-                    .child(div().child(self.data.name.clone()).text_size(px(16.)))
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .child(
+                                div()
+                                    .map(|this| match title_match {
+                                        Some(text) => this.child(text),
+                                        None => this.child(title),
+                                    })
+                                    .text_size(px(16.)),
+                            )
+                            .when(self.data.is_renamed(), |this| {
+                                this.child(
+                                    div()
+                                        .child(self.data.name.clone())
+                                        .text_size(px(13.))
+                                        .text_color(cx.theme().muted_foreground),
+                                )
+                            })
+                            .when(self.data.unavailable, |this| {
+                                this.child(
+                                    div()
+                                        .child("offline")
+                                        .text_size(px(11.))
+                                        .px_1()
+                                        .rounded_sm()
+                                        .border_1()
+                                        .border_color(cx.theme().border)
+                                        .text_color(cx.theme().muted_foreground),
+                                )
+                            })
+                            .when_some(self.data.parent_name(), |this, parent| {
+                                this.child(
+                                    div()
+                                        .child(format!("in {}", parent))
+                                        .text_size(px(13.))
+                                        .text_color(cx.theme().muted_foreground),
+                                )
+                            })
+                            .children(self.data.project_types.iter().map(|kind| {
+                                div()
+                                    .child(kind.label())
+                                    .text_size(px(11.))
+                                    .px_1()
+                                    .rounded_sm()
+                                    .border_1()
+                                    .border_color(cx.theme().border)
+                                    .text_color(cx.theme().muted_foreground)
+                            }))
+                            .children(self.data.tags().iter().map(|tag| {
+                                div()
+                                    .child(tag.clone())
+                                    .text_size(px(11.))
+                                    .px_1()
+                                    .rounded_sm()
+                                    .bg(cx.theme().secondary)
+                                    .text_color(cx.theme().secondary_foreground)
+                            })),
+                    )
                     .child(
                         div()
-                            .child(self.data.path.clone())
+                            .map(|this| match path_match {
+                                Some(text) => this.child(text),
+                                None => this.child(self.data.path.clone()),
+                            })
                             .text_size(px(14.))
                             .text_color(cx.theme().muted_foreground),
                     )
+                    .when(!bar.is_empty(), |this| {
+                        this.child(
+                            h_flex()
+                                .mt_1()
+                                .h(px(4.))
+                                .w_full()
+                                .rounded_sm()
+                                .overflow_hidden()
+                                .children(bar.into_iter().map(|(language, share)| {
+                                    div()
+                                        .h_full()
+                                        .w(relative(share as f32))
+                                        .bg(rgb(language_color(&language)))
+                                })),
+                        )
+                    })
                     .flex_grow(),
             )
             .child(
-                div()
-                    .child(format!("{}", self.data.count))
-                    .text_size(px(14.))
-                    .text_color(cx.theme().muted_foreground),
+                v_flex()
+                    .items_end()
+                    .child(
+                        div()
+                            .child(format!("{}", self.data.count))
+                            .text_size(px(14.))
+                            .text_color(cx.theme().muted_foreground),
+                    )
+                    .when_some(self.data.activity.as_ref(), |this, activity| {
+                        let weekly = activity.weekly(now_secs(), ACTIVITY_WEEKS);
+                        let max = weekly.iter().copied().max().unwrap_or_default().max(1);
+                        let recent = activity.commits_within(now_secs(), 30);
+                        this.child(
+                            h_flex()
+                                .gap_1()
+                                .child(
+                                    div()
+                                        .child(format!("{} in 30d", recent))
+                                        .text_size(px(11.))
+                                        .text_color(cx.theme().muted_foreground),
+                                )
+                                .child(h_flex().items_end().h(px(10.)).gap(px(1.)).children(
+                                    weekly.into_iter().map(|commits| {
+                                        div()
+                                            .w(px(2.))
+                                            .h(relative((commits as f32 / max as f32).max(0.1)))
+                                            .bg(cx.theme().muted_foreground)
+                                    }),
+                                )),
+                        )
+                    })
+                    .when_some(self.data.disk_usage, |this, usage| {
+                        let size = if usage.artifacts > 0 {
+                            format!(
+                                "{} · {} build",
+                                format_size(usage.total()),
+                                format_size(usage.artifacts)
+                            )
+                        } else {
+                            format_size(usage.total())
+                        };
+                        this.child(
+                            div()
+                                .child(size)
+                                .text_size(px(11.))
+                                .text_color(cx.theme().muted_foreground),
+                        )
+                    })
+                    .when(self.selected, |this| {
+                        this.children(self.data.actions().iter().take(9).enumerate().map(
+                            |(index, action)| {
+                                div()
+                                    .child(format!("⌘{} {}", index + 1, action.name))
+                                    .text_size(px(11.))
+                                    .text_color(cx.theme().muted_foreground)
+                            },
+                        ))
+                    }),
             )
             .pt_2()
             .pb_2()
             .pl_4()
             .pr_4()
             .cursor_pointer()
+            .when(self.selected, |this| this.bg(cx.theme().list_active))
+            .when(self.data.archived, |this| this.opacity(0.6))
+            .when(self.data.unavailable, |this| {
+                this.opacity(0.4).cursor_default()
+            })
             .hover(|style| style.bg(cx.theme().list_hover))
     }
 }
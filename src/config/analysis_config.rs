@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+/// io scheduling class of analysis threads
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IoPriority {
+    Normal,
+    /// lowest best-effort priority
+    #[default]
+    Low,
+    /// only when no other process uses the disk
+    Idle,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GitLauncherAnalysisConfig {
+    /// repos with more source files are analysed from a sample, 0 for no limit
+    pub max_files: usize,
+    /// repos with more bytes of source are analysed from a sample, 0 for no limit
+    pub max_bytes: u64,
+    /// stop and keep what was counted after this many seconds, 0 for no limit
+    pub timeout_secs: u64,
+    /// cpu niceness of analysis threads, 0 to 19
+    pub nice: i32,
+    pub io_priority: IoPriority,
+}
+
+impl Default for GitLauncherAnalysisConfig {
+    fn default() -> Self {
+        Self {
+            max_files: 20_000,
+            max_bytes: 256 * 1024 * 1024,
+            timeout_secs: 60,
+            nice: 10,
+            io_priority: IoPriority::Low,
+        }
+    }
+}
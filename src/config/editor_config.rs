@@ -1,14 +1,32 @@
 use serde::{Deserialize, Serialize};
 
+/// what gets opened for a workspace member
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemberTarget {
+    /// the member directory on its own
+    #[default]
+    Member,
+    /// the workspace root, with the member's manifest opened in it
+    Root,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GitLauncherEditorConfig {
     pub editor: String,
+    pub member_target: MemberTarget,
+    /// editors a repo's `.git-launcher.toml` may pick besides `editor`, a
+    /// cloned repo must not be able to launch any program it names
+    pub allowed_editors: Vec<String>,
 }
 
 impl Default for GitLauncherEditorConfig {
     fn default() -> Self {
         Self {
             editor: "/Applications/Visual Studio Code.app".to_string(),
+            member_target: MemberTarget::default(),
+            allowed_editors: Vec::new(),
         }
     }
 }
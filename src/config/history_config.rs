@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GitLauncherHistoryConfig {
+    /// rotate `history.jsonl` once it grows beyond this many bytes
+    pub max_file_size: u64,
+    /// how many rotated history files are kept
+    pub max_files: usize,
+    /// how many repos the `:recent` view lists
+    pub recent_count: usize,
+}
+
+impl Default for GitLauncherHistoryConfig {
+    fn default() -> Self {
+        Self {
+            max_file_size: 1024 * 1024,
+            max_files: 5,
+            recent_count: 20,
+        }
+    }
+}
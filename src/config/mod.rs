@@ -4,12 +4,22 @@ use anyhow::Ok;
 use gpui::*;
 use serde::{Deserialize, Serialize};
 
+use crate::state_file;
+
+mod analysis_config;
 mod editor_config;
+mod history_config;
+mod ranking_config;
 mod repo_config;
+mod storage_config;
 mod ui_config;
 
+pub use analysis_config::*;
 pub use editor_config::*;
+pub use history_config::*;
+pub use ranking_config::*;
 pub use repo_config::*;
+pub use storage_config::*;
 pub use ui_config::*;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +27,14 @@ pub struct Config {
     pub repo_config: GitLauncherConfig,
     pub ui_config: GitLauncherUIConfig,
     pub editor_config: GitLauncherEditorConfig,
+    #[serde(default)]
+    pub ranking_config: GitLauncherRankingConfig,
+    #[serde(default)]
+    pub history_config: GitLauncherHistoryConfig,
+    #[serde(default)]
+    pub storage_config: GitLauncherStorageConfig,
+    #[serde(default)]
+    pub analysis_config: GitLauncherAnalysisConfig,
 }
 
 pub(crate) static CONFIG_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
@@ -29,27 +47,50 @@ pub(crate) static SETTING_PATH: LazyLock<PathBuf> =
 
 pub(crate) static REPO_PATH: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_PATH.join("repo.json"));
 
-// init config with default or config file
-pub fn init(cx: &mut App) -> Result<(), anyhow::Error> {
+#[cfg(feature = "sqlite")]
+pub(crate) static SQLITE_PATH: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_PATH.join("repo.db"));
+
+pub(crate) static CACHE_PATH: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_PATH.join("cache"));
+
+pub(crate) static USER_ICONS_PATH: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_PATH.join("icons"));
+
+pub(crate) static HISTORY_PATH: LazyLock<PathBuf> =
+    LazyLock::new(|| CONFIG_PATH.join("history.jsonl"));
+
+pub(crate) static TRUST_PATH: LazyLock<PathBuf> =
+    LazyLock::new(|| CONFIG_PATH.join("trusted_actions.json"));
+
+// load config with default or config file
+pub fn load() -> Result<Config, anyhow::Error> {
     fs::create_dir_all(CONFIG_PATH.clone())?;
 
     let mut config = Config {
         repo_config: GitLauncherConfig::default(),
         ui_config: GitLauncherUIConfig::default(),
         editor_config: GitLauncherEditorConfig::default(),
+        ranking_config: GitLauncherRankingConfig::default(),
+        history_config: GitLauncherHistoryConfig::default(),
+        storage_config: GitLauncherStorageConfig::default(),
+        analysis_config: GitLauncherAnalysisConfig::default(),
     };
 
     if SETTING_PATH.exists() {
-        let setting = toml::from_str(&fs::read_to_string(SETTING_PATH.clone())?)?;
+        let setting = toml::from_str(&state_file::read(&SETTING_PATH)?)?;
         config = setting;
     } else {
-        fs::write(SETTING_PATH.clone(), toml::to_string(&config)?)?;
+        state_file::write(&SETTING_PATH, toml::to_string(&config)?.as_bytes())?;
     }
 
     if !REPO_PATH.exists() {
-        fs::write(REPO_PATH.clone(), "")?;
+        state_file::write(&REPO_PATH, b"")?;
     }
 
+    Ok(config)
+}
+
+// init config with default or config file
+pub fn init(cx: &mut App) -> Result<(), anyhow::Error> {
+    let config = load()?;
     cx.set_global(config);
 
     Ok(())
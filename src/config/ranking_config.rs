@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GitLauncherRankingConfig {
+    /// hours after which an open counts half as much as a fresh one
+    pub half_life_hours: f64,
+    /// how many recent open timestamps are kept per repo
+    pub max_samples: usize,
+}
+
+impl Default for GitLauncherRankingConfig {
+    fn default() -> Self {
+        Self {
+            half_life_hours: 24.0 * 7.0,
+            max_samples: 10,
+        }
+    }
+}
@@ -1,11 +1,14 @@
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GitLauncherConfig {
     pub base_dir: Vec<String>,
     pub ignore_dirs: Vec<String>,
     pub max_depth: usize,
     pub max_concurrent_tasks: usize,
+    /// list packages of cargo/npm/pnpm/yarn/go/nx workspaces as their own entries
+    pub discover_members: bool,
 }
 
 impl Default for GitLauncherConfig {
@@ -22,6 +25,7 @@ impl Default for GitLauncherConfig {
             ],
             max_depth: 10,
             max_concurrent_tasks: 20,
+            discover_members: true,
         }
     }
 }
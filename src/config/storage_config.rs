@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// `repo.json`, rewritten whole on every change
+    #[default]
+    Json,
+    /// `repo.db`, needs the `sqlite` feature
+    Sqlite,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GitLauncherStorageConfig {
+    pub backend: StorageBackend,
+}
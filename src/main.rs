@@ -1,7 +1,7 @@
 use crate::{
     component::GitLauncher,
-    config::{Config, REPO_PATH},
-    repo::{GitProjectFinder, Repo, RepoState},
+    config::Config,
+    repo::{AnalysisQueue, RepoState},
 };
 use global_hotkey::{
     GlobalHotKeyEvent, GlobalHotKeyManager,
@@ -9,15 +9,23 @@ use global_hotkey::{
 };
 use gpui::*;
 use gpui_component::Root;
-use std::{fs, path::Path, sync::mpsc};
-use std::{sync::LazyLock, time::Duration};
+use std::sync::mpsc;
 use std::{sync::RwLock, thread::spawn};
+use std::{
+    sync::{
+        Arc, LazyLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
 use tokio::runtime::Runtime;
 
 mod assets;
+mod cli;
 mod component;
 mod config;
 mod repo;
+mod state_file;
 mod system;
 
 actions!(git_launcher, [Quit, ShowWindow]);
@@ -27,6 +35,9 @@ pub(crate) static GLOBAL_APP_STATE: LazyLock<RwLock<AppState>> =
 
 pub(crate) static GLOBAL_RUNTIME: LazyLock<Runtime> = LazyLock::new(|| Runtime::new().unwrap());
 
+/// how often sizes are checked for entries that are older than a day
+const MEASURE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
 struct AppState {
     hot_key_manager: GlobalHotKeyManager,
     window_handle: Option<WindowHandle<Root>>,
@@ -66,6 +77,15 @@ impl AppState {
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(result) = cli::run(&args) {
+        if let Err(err) = result {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let app = Application::new().with_assets(assets::Assets);
 
     {
@@ -108,107 +128,135 @@ fn main() {
 
         cx.activate(true);
 
-        cx.spawn(async move |cx| {
-            let config = cx
-                .read_global(|state: &Config, _: &App| state.repo_config.clone())
+        cx.spawn(async move |cx| -> anyhow::Result<()> {
+            let (config, analysis_config) = cx
+                .read_global(|state: &Config, _: &App| {
+                    (state.repo_config.clone(), state.analysis_config)
+                })
                 .unwrap();
 
-            let content = fs::read_to_string(REPO_PATH.clone()).unwrap();
+            let store = repo::storage().load()?;
+            if store.needs_scan {
+                eprintln!("repo index is empty or unreadable, rebuilding it");
+            }
 
-            if content.is_empty() {
-                GLOBAL_RUNTIME.block_on(async move {
-                    let repo_finder = GitProjectFinder::builder(config.clone()).build();
+            cx.update_global(|state: &mut RepoState, _: &mut App| {
+                let mut repo_state = state.repos.write().unwrap();
+                for repo in store.repos {
+                    repo_state.insert(repo);
+                }
+            })
+            .unwrap();
+
+            let queue = cx.read_global(|queue: &AnalysisQueue, _: &App| queue.clone())?;
+            let repos = cx.read_global(|state: &RepoState, _: &App| state.clone())?;
+            GLOBAL_RUNTIME.spawn(queue.clone().run(
+                repos,
+                config.max_concurrent_tasks,
+                analysis_config,
+            ));
+
+            // scan on startup and again whenever a base dir is plugged in,
+            // unplugged or shows up at another mount path
+            let mut volumes = None;
+            // sizes are measured after every scan and checked hourly, one
+            // measurement at a time
+            let measuring = Arc::new(AtomicBool::new(false));
+            let mut measured_at: Option<Instant> = None;
+            loop {
+                let known = cx.read_global(|state: &RepoState, _: &App| {
+                    state.repos.read().unwrap().clone()
+                })?;
+                let base_dirs = config.base_dir.clone();
+                let resolved = cx
+                    .background_spawn(async move { repo::resolve_volumes(&base_dirs, &known) })
+                    .await;
 
-                    for dir in config.base_dir.clone() {
-                        let repos = repo_finder
-                            .find_git_projects(Path::new(&dir.clone()))
-                            .await
-                            .unwrap();
+                if volumes.as_ref() != Some(&resolved) {
+                    let scan_config = config.clone();
+                    let scan_volumes = resolved.clone();
+                    let scanned = cx
+                        .background_spawn(async move {
+                            GLOBAL_RUNTIME.block_on(repo::scan(&scan_config, &scan_volumes))
+                        })
+                        .await;
 
+                    // a failed scan, e.g. of a drive that goes away mid-scan,
+                    // is retried on the next tick
+                    let state = scanned.and_then(|scanned| {
                         cx.update_global(|state: &mut RepoState, _: &mut App| {
-                            let mut repo_state = state.repos.write().unwrap();
-                            for repo in repos {
-                                repo_state.insert(Repo {
-                                    name: repo.folder_name.clone(),
-                                    path: repo.full_path.to_string_lossy().to_string().clone(),
-                                    language: String::from("unknown"),
-                                    count: 0,
-                                });
-                            }
-                        })
-                        .unwrap();
-                    }
+                            state.merge_scan(scanned, &resolved).map(|_| state.clone())
+                        })?
+                    });
+                    let state = match state {
+                        Ok(state) => state,
+                        Err(err) => {
+                            eprintln!("failed to scan repos: {}", err);
+                            cx.background_executor()
+                                .timer(Duration::from_secs(10))
+                                .await;
+                            continue;
+                        }
+                    };
+                    queue.push_all(&state);
 
-                    let repo_state = cx
-                        .read_global(|state: &RepoState, _: &App| state.repos.clone())
-                        .unwrap();
-
-                    fs::write(
-                        REPO_PATH.clone(),
-                        serde_json::to_string(&repo_state).unwrap(),
-                    )
-                    .unwrap();
-                });
-            } else {
-                let repo_state = fs::read_to_string(REPO_PATH.clone()).unwrap();
-                let repos: Vec<Repo> = serde_json::from_str(&repo_state).unwrap();
-                cx.update_global(|state: &mut RepoState, _: &mut App| {
-                    let mut repo_state = state.repos.write().unwrap();
-                    for repo in repos {
-                        repo_state.insert(repo);
-                    }
-                })
-                .unwrap();
+                    measured_at = None;
+                    volumes = Some(resolved);
+                }
 
-                let new_repos = cx
-                    .background_spawn(async move {
-                        let ret: Result<Vec<Repo>, anyhow::Error> =
-                            GLOBAL_RUNTIME.block_on(async move {
-                                let mut new_repos = Vec::new();
-                                let repo_finder = GitProjectFinder::builder(config.clone()).build();
-
-                                for dir in config.base_dir.clone() {
-                                    repo_finder
-                                        .find_git_projects(Path::new(&dir.clone()))
-                                        .await
-                                        .unwrap()
-                                        .into_iter()
-                                        .for_each(|re| {
-                                            new_repos.push(Repo {
-                                                name: re.folder_name.clone(),
-                                                path: re
-                                                    .full_path
-                                                    .to_string_lossy()
-                                                    .to_string()
-                                                    .clone(),
-                                                language: String::from("unknown"),
-                                                count: 0,
-                                            });
-                                        });
-                                }
-
-                                fs::write(
-                                    REPO_PATH.clone(),
-                                    serde_json::to_string(&new_repos).unwrap(),
-                                )
-                                .unwrap();
-                                Ok(new_repos)
-                            });
-
-                        ret
-                    })
-                    .await?;
-
-                cx.update_global(|state: &mut RepoState, _: &mut App| {
-                    let mut repo_state = state.repos.write().unwrap();
-                    for repo in new_repos {
-                        repo_state.insert(repo);
-                    }
-                })
-                .unwrap();
+                if measured_at.is_none_or(|at| at.elapsed() >= MEASURE_INTERVAL)
+                    && !measuring.swap(true, Ordering::SeqCst)
+                {
+                    measured_at = Some(Instant::now());
+                    let state = cx.read_global(|state: &RepoState, _: &App| state.clone())?;
+                    let measure_config = config.clone();
+                    let measuring = measuring.clone();
+                    GLOBAL_RUNTIME.spawn(async move {
+                        let result = repo::measure_disk_usage(
+                            &state,
+                            &measure_config.ignore_dirs,
+                            measure_config.max_concurrent_tasks,
+                            false,
+                        )
+                        .await;
+                        if let Err(err) = result {
+                            eprintln!("failed to measure disk usage: {}", err);
+                        }
+                        measuring.store(false, Ordering::SeqCst);
+                    });
+                }
+
+                cx.background_executor()
+                    .timer(Duration::from_secs(10))
+                    .await;
             }
+        })
+        .detach();
 
-            Ok::<_, anyhow::Error>(())
+        // reload the repo index when another process (e.g. the cli) changes it
+        cx.spawn(async move |cx| {
+            loop {
+                cx.background_executor().timer(Duration::from_secs(1)).await;
+
+                if !repo::storage().poll_changed() {
+                    continue;
+                }
+
+                let store = match repo::storage().load() {
+                    Ok(store) => store,
+                    Err(err) => {
+                        eprintln!("failed to reload repo index: {}", err);
+                        continue;
+                    }
+                };
+
+                if cx
+                    .update_global(|state: &mut RepoState, _: &mut App| state.reload(store.repos))
+                    .is_err()
+                {
+                    break;
+                }
+            }
         })
         .detach();
 
@@ -0,0 +1,223 @@
+use std::{collections::HashMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use super::{git, now_secs};
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+/// commits per day are kept for this many days before the analysis
+const KEPT_DAYS: u64 = 365;
+const TOP_CONTRIBUTORS: usize = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contributor {
+    pub name: String,
+    pub commits: u32,
+}
+
+/// commit history of a repo up to HEAD, read from the local object database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Activity {
+    /// unix seconds of the oldest and newest commit
+    pub first_commit: u64,
+    pub last_commit: u64,
+    pub commits: u32,
+    /// authors with the most commits in the year before the analysis, by
+    /// mailmap name
+    pub contributors: Vec<Contributor>,
+    /// `(unix day, commits)` of every day with commits in the year before
+    /// the analysis, oldest first
+    pub days: Vec<(u64, u32)>,
+}
+
+impl Activity {
+    /// `None` outside of git or without commits. members only count commits
+    /// touching their directory, their first commit is the repo's. only the
+    /// last year of the log is read, older history is just counted
+    pub fn read(path: &Path) -> Option<Self> {
+        let since = now_secs().saturating_sub(KEPT_DAYS * DAY_SECS);
+        let commits: u32 = git(path, &["rev-list", "--count", "HEAD", "--", "."])?
+            .trim()
+            .parse()
+            .ok()?;
+        if commits == 0 {
+            return None;
+        }
+        let last_commit: u64 = git(path, &["log", "-1", "--format=%ct", "HEAD", "--", "."])?
+            .trim()
+            .parse()
+            .ok()?;
+        // root commits, without a pathspec git doesn't diff every commit
+        let first_commit = git(path, &["log", "--max-parents=0", "--format=%ct", "HEAD"])?
+            .lines()
+            .filter_map(|line| line.parse().ok())
+            .min()
+            .unwrap_or(last_commit);
+        let log = git(
+            path,
+            &[
+                "log",
+                &format!("--max-age={since}"),
+                "--format=%ct%x09%aN",
+                "HEAD",
+                "--",
+                ".",
+            ],
+        )?;
+
+        let mut authors: HashMap<&str, u32> = HashMap::new();
+        let mut days: HashMap<u64, u32> = HashMap::new();
+        for line in log.lines() {
+            let Some((time, author)) = line.split_once('\t') else {
+                continue;
+            };
+            let Ok(time) = time.parse::<u64>() else {
+                continue;
+            };
+            *authors.entry(author).or_default() += 1;
+            *days.entry(time / DAY_SECS).or_default() += 1;
+        }
+
+        let mut contributors: Vec<Contributor> = authors
+            .into_iter()
+            .map(|(name, commits)| Contributor {
+                name: name.to_string(),
+                commits,
+            })
+            .collect();
+        contributors.sort_by(|a, b| b.commits.cmp(&a.commits).then_with(|| a.name.cmp(&b.name)));
+        contributors.truncate(TOP_CONTRIBUTORS);
+
+        let mut days: Vec<(u64, u32)> = days.into_iter().collect();
+        days.sort_unstable();
+
+        Some(Self {
+            first_commit: first_commit.min(last_commit),
+            last_commit,
+            commits,
+            contributors,
+            days,
+        })
+    }
+
+    /// commits in the last `days` days before `now`
+    pub fn commits_within(&self, now: u64, days: u64) -> u32 {
+        let since = now.saturating_sub(days * DAY_SECS) / DAY_SECS;
+        self.days
+            .iter()
+            .filter(|(day, _)| *day >= since)
+            .map(|(_, commits)| commits)
+            .sum()
+    }
+
+    /// commits per week over the last `weeks` weeks, oldest first
+    pub fn weekly(&self, now: u64, weeks: usize) -> Vec<u32> {
+        let today = now / DAY_SECS;
+        let mut counts = vec![0; weeks];
+        for (day, commits) in &self.days {
+            let week = (today.saturating_sub(*day) / 7) as usize;
+            if week < weeks {
+                counts[weeks - 1 - week] += commits;
+            }
+        }
+        counts
+    }
+}
+
+/// `▁▂▅█`-style line of `values`, scaled to the largest one
+pub fn sparkline(values: &[u32]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = values.iter().copied().max().unwrap_or_default().max(1);
+    values
+        .iter()
+        .map(|value| BARS[(value * 7).div_ceil(max) as usize])
+        .collect()
+}
+
+/// a duration like `3d`, for "3d ago"
+pub fn format_age(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+/// unix seconds as a utc `YYYY-MM-DD`
+pub fn format_date(secs: u64) -> String {
+    // days to civil date, from Howard Hinnant's date algorithms
+    let days = (secs / DAY_SECS) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// noon on 2024-03-10, a sunday
+    const NOW: u64 = 1_710_072_000;
+
+    fn activity(days_ago: &[(u64, u32)]) -> Activity {
+        let today = NOW / DAY_SECS;
+        Activity {
+            first_commit: 0,
+            last_commit: NOW,
+            commits: days_ago.iter().map(|(_, commits)| commits).sum(),
+            contributors: Vec::new(),
+            days: days_ago
+                .iter()
+                .rev()
+                .map(|(ago, commits)| (today - ago, *commits))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn commits_within_counts_whole_days() {
+        let activity = activity(&[(0, 1), (6, 2), (7, 4), (29, 8), (200, 16)]);
+        assert_eq!(activity.commits_within(NOW, 1), 1);
+        assert_eq!(activity.commits_within(NOW, 7), 7);
+        assert_eq!(activity.commits_within(NOW, 30), 15);
+        assert_eq!(activity.commits_within(NOW, 365), 31);
+    }
+
+    #[test]
+    fn weekly_buckets_end_today() {
+        let activity = activity(&[(0, 1), (6, 2), (7, 4), (20, 8), (400, 16)]);
+        assert_eq!(activity.weekly(NOW, 3), [8, 4, 3]);
+        assert_eq!(activity.weekly(NOW, 1), [3]);
+        assert_eq!(activity.weekly(NOW, 0), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn sparkline_scales_to_the_largest_value() {
+        assert_eq!(sparkline(&[0, 1, 4, 7]), "▁▂▅█");
+        assert_eq!(sparkline(&[0, 0]), "▁▁");
+        assert_eq!(sparkline(&[3]), "█");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn formats_utc_dates() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(NOW), "2024-03-10");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_735_689_599), "2024-12-31");
+        assert_eq!(format_age(59), "59s");
+        assert_eq!(format_age(3 * 86_400 + 5), "3d");
+    }
+}
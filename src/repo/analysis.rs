@@ -0,0 +1,233 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use gpui::Global;
+use tokio::sync::{Notify, Semaphore, watch};
+
+use crate::config::GitLauncherAnalysisConfig;
+
+use super::{Analysis, RepoState, analyze, now_secs};
+
+/// finished analyses are written to the index in batches of this many
+const FLUSH_BATCH: usize = 32;
+/// or at the latest after this long
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// a repo waiting for analysis, `force` skips the cache
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Job {
+    path: String,
+    force: bool,
+}
+
+/// a finished job, `None` when the analysis failed
+type Finished = (String, Option<Analysis>);
+
+/// repos waiting for language analysis, worked off in the background by
+/// [`AnalysisQueue::run`] with at most `max_concurrent_tasks` analyses at a time
+#[derive(Clone)]
+pub struct AnalysisQueue {
+    pending: Arc<Mutex<VecDeque<Job>>>,
+    added: Arc<Notify>,
+    paused: watch::Sender<bool>,
+    /// bumped after every batch of finished analyses was written
+    updated: watch::Sender<u64>,
+}
+
+impl Global for AnalysisQueue {}
+
+impl Default for AnalysisQueue {
+    fn default() -> Self {
+        Self {
+            pending: Arc::default(),
+            added: Arc::default(),
+            paused: watch::Sender::new(false),
+            updated: watch::Sender::new(0),
+        }
+    }
+}
+
+impl AnalysisQueue {
+    /// queue a repo at the back, repos already waiting keep their place
+    pub fn push(&self, path: &str) {
+        let mut pending = self.pending.lock().unwrap();
+        if !pending.iter().any(|queued| queued.path == path) {
+            pending.push_back(Job {
+                path: path.to_string(),
+                force: false,
+            });
+            self.added.notify_one();
+        }
+    }
+
+    /// queue a repo ahead of everything else, e.g. right after it was opened
+    pub fn push_front(&self, path: &str) {
+        self.push_front_job(path, false);
+    }
+
+    /// analyse a repo again even if its cache entry is current
+    pub fn reanalyze(&self, path: &str) {
+        self.push_front_job(path, true);
+    }
+
+    fn push_front_job(&self, path: &str, force: bool) {
+        let mut pending = self.pending.lock().unwrap();
+        let force = force
+            || pending
+                .iter()
+                .any(|queued| queued.path == path && queued.force);
+        pending.retain(|queued| queued.path != path);
+        pending.push_front(Job {
+            path: path.to_string(),
+            force,
+        });
+        self.added.notify_one();
+    }
+
+    /// queue every reachable repo, never analysed ones first. repos that did
+    /// not change since their last analysis are answered from the cache
+    pub fn push_all(&self, state: &RepoState) {
+        let repos = state.repos.read().unwrap();
+        let mut repos: Vec<_> = repos.iter().filter(|repo| !repo.unavailable).collect();
+        repos.sort_by_key(|repo| repo.analyzed_at);
+
+        for repo in repos {
+            self.push(&repo.path);
+        }
+    }
+
+    /// stop starting new analyses, running ones finish
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.send_replace(paused);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// resolves once the queue is not paused
+    pub async fn resumed(&self) {
+        let _ = self.paused.subscribe().wait_for(|paused| !*paused).await;
+    }
+
+    /// resolves whenever finished analyses were written to `RepoState`
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.updated.subscribe()
+    }
+
+    fn pop(&self) -> Option<Job> {
+        self.pending.lock().unwrap().pop_front()
+    }
+
+    /// work off the queue forever, runs on the tokio runtime
+    pub async fn run(
+        self,
+        state: RepoState,
+        max_concurrent_tasks: usize,
+        config: GitLauncherAnalysisConfig,
+    ) {
+        let semaphore = Arc::new(Semaphore::new(max_concurrent_tasks.max(1)));
+        let mut paused = self.paused.subscribe();
+        let finished: Arc<Mutex<Vec<Finished>>> = Arc::default();
+
+        // writes what the last batch didn't fill up
+        tokio::spawn({
+            let (state, finished, updated) =
+                (state.clone(), finished.clone(), self.updated.clone());
+            async move {
+                loop {
+                    tokio::time::sleep(FLUSH_INTERVAL).await;
+                    flush(&state, &finished, &updated);
+                }
+            }
+        });
+
+        loop {
+            if paused.wait_for(|paused| !*paused).await.is_err() {
+                return;
+            }
+            let Ok(permit) = semaphore.clone().acquire_owned().await else {
+                return;
+            };
+            let Some(job) = self.pop() else {
+                drop(permit);
+                self.added.notified().await;
+                continue;
+            };
+
+            let state = state.clone();
+            let updated = self.updated.clone();
+            let finished = finished.clone();
+            tokio::spawn(async move {
+                let (path, force) = (job.path.clone(), job.force);
+                let analysis = match tokio::task::spawn_blocking(move || {
+                    analyze(&job.path, job.force, &config)
+                })
+                .await
+                {
+                    Ok(Ok(analysis)) => Some(analysis),
+                    Ok(Err(err)) => {
+                        eprintln!("failed to analyse {}: {}", path, err);
+                        None
+                    }
+                    Err(err) => {
+                        eprintln!("failed to analyse {}: {}", path, err);
+                        None
+                    }
+                };
+
+                // cache hits on startup would otherwise rewrite the index once per repo
+                let unchanged = state.repos.read().unwrap().iter().any(|repo| {
+                    repo.path == path
+                        && repo.analyzed_at.is_some()
+                        && analysis.as_ref().is_none_or(|analysis| {
+                            analysis.languages == repo.languages
+                                && analysis.partial == repo.languages_partial
+                                && analysis.activity == repo.activity
+                        })
+                });
+                if unchanged && !force {
+                    drop(permit);
+                    return;
+                }
+
+                let full = {
+                    let mut finished = finished.lock().unwrap();
+                    finished.push((path, analysis));
+                    finished.len() >= FLUSH_BATCH
+                };
+                if full {
+                    flush(&state, &finished, &updated);
+                }
+                drop(permit);
+            });
+        }
+    }
+}
+
+/// write every finished analysis to the index in one transaction
+fn flush(state: &RepoState, finished: &Mutex<Vec<Finished>>, updated: &watch::Sender<u64>) {
+    let batch = std::mem::take(&mut *finished.lock().unwrap());
+    if batch.is_empty() {
+        return;
+    }
+
+    let mut batch: HashMap<String, Option<Analysis>> = batch.into_iter().collect();
+    let analyzed_at = now_secs();
+    let result = state.update_all(|repo| {
+        if let Some(analysis) = batch.remove(&repo.path) {
+            if let Some(analysis) = analysis {
+                repo.set_analysis(analysis);
+            }
+            repo.analyzed_at = Some(analyzed_at);
+        }
+    });
+    if let Err(err) = result {
+        eprintln!("failed to save repo state: {}", err);
+    }
+
+    updated.send_modify(|generation| *generation += 1);
+}
@@ -0,0 +1,203 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{CACHE_PATH, GitLauncherAnalysisConfig};
+
+use super::{
+    Activity, CodeStats, LanguageAnalyzer, LanguageStat, now_secs, storage, with_priority,
+};
+
+/// bump when the analysis output changes so old entries are ignored
+const CACHE_VERSION: u32 = 8;
+
+/// what a repo looked like when it was analysed: the HEAD commit plus a
+/// fingerprint of uncommitted changes. `None` outside of git, those repos are
+/// analysed every time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnalysisKey {
+    pub head: String,
+    pub dirty: u64,
+}
+
+impl AnalysisKey {
+    pub fn of(path: &Path) -> Option<Self> {
+        let head = git(path, &["rev-parse", "HEAD"])?;
+        let status = git(
+            path,
+            &[
+                "status",
+                "--porcelain=v1",
+                "-z",
+                "--untracked-files=normal",
+                "--",
+                ".",
+            ],
+        )?;
+
+        // the status lists which files changed, their size and mtime catch
+        // further edits to files that were already dirty
+        // porcelain paths are relative to the top level, not to `path`
+        let top = git(path, &["rev-parse", "--show-toplevel"])?;
+        let top = Path::new(top.trim());
+
+        let mut dirty = fnv1a(FNV_OFFSET, status.as_bytes());
+        for entry in status.split('\0') {
+            let Some(file) = entry.get(3..).filter(|file| !file.is_empty()) else {
+                continue;
+            };
+            if let Ok(meta) = fs::metadata(top.join(file)) {
+                dirty = fnv1a(dirty, &meta.len().to_le_bytes());
+                if let Ok(modified) = meta.modified()
+                    && let Ok(since) = modified.duration_since(std::time::UNIX_EPOCH)
+                {
+                    dirty = fnv1a(dirty, &since.as_nanos().to_le_bytes());
+                }
+            }
+        }
+
+        Some(Self {
+            head: head.trim().to_string(),
+            dirty,
+        })
+    }
+}
+
+pub(super) fn git(path: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(args)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+pub(super) const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+
+/// stable across builds, unlike `DefaultHasher`
+pub(super) fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// the language breakdown and commit activity of a repo
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Analysis {
+    pub languages: Vec<LanguageStat>,
+    /// counted from a sample because the repo exceeds the analysis limits
+    pub partial: bool,
+    pub activity: Option<Activity>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    version: u32,
+    path: String,
+    key: AnalysisKey,
+    #[serde(flatten)]
+    analysis: Analysis,
+    /// the per-file breakdown behind `analysis`, for the detail pane
+    #[serde(default)]
+    stats: Option<CodeStats>,
+    analyzed_at: u64,
+}
+
+/// one json file per repo under `~/.git-launcher/cache/analysis`, for
+/// backends without a cache of their own
+pub(super) fn analysis_file(path: &str) -> PathBuf {
+    CACHE_PATH
+        .join("analysis")
+        .join(format!("{:016x}.json", fnv1a(FNV_OFFSET, path.as_bytes())))
+}
+
+fn read_cache(path: &str, key: &AnalysisKey) -> Option<CacheEntry> {
+    let content = storage().read_analysis(path)?;
+    let entry: CacheEntry = serde_json::from_str(&content).ok()?;
+    (entry.version == CACHE_VERSION && entry.path == path && entry.key == *key).then_some(entry)
+}
+
+fn write_cache(
+    path: &str,
+    key: AnalysisKey,
+    analysis: &Analysis,
+    stats: &CodeStats,
+) -> anyhow::Result<()> {
+    let cache_key = serde_json::to_string(&key)?;
+    let entry = CacheEntry {
+        version: CACHE_VERSION,
+        path: path.to_string(),
+        key,
+        analysis: analysis.clone(),
+        stats: Some(stats.clone()),
+        analyzed_at: now_secs(),
+    };
+    storage().write_analysis(path, &cache_key, &serde_json::to_string(&entry)?)
+}
+
+/// the language breakdown and activity, from the cache unless the HEAD commit or the
+/// uncommitted changes differ, or `force` is set. new analyses keep to the
+/// limits and priority of `config`
+pub fn analyze(
+    path: &str,
+    force: bool,
+    config: &GitLauncherAnalysisConfig,
+) -> anyhow::Result<Analysis> {
+    let key = AnalysisKey::of(Path::new(path));
+    if !force
+        && let Some(key) = &key
+        && let Some(cached) = read_cache(path, key)
+    {
+        return Ok(cached.analysis);
+    }
+    Ok(run_analysis(path, key, config)?.0)
+}
+
+/// the full code stats of the detail pane, cached with the analysis
+pub fn code_stats(path: &str, config: &GitLauncherAnalysisConfig) -> anyhow::Result<CodeStats> {
+    if let Some(stats) = cached_code_stats(path) {
+        return Ok(stats);
+    }
+    Ok(run_analysis(path, AnalysisKey::of(Path::new(path)), config)?.1)
+}
+
+/// the code stats of the last analysis, if the repo did not change since
+pub fn cached_code_stats(path: &str) -> Option<CodeStats> {
+    let key = AnalysisKey::of(Path::new(path))?;
+    read_cache(path, &key)?.stats
+}
+
+fn run_analysis(
+    path: &str,
+    key: Option<AnalysisKey>,
+    config: &GitLauncherAnalysisConfig,
+) -> anyhow::Result<(Analysis, CodeStats)> {
+    let (stats, activity) = with_priority(config, || {
+        (
+            LanguageAnalyzer::new(path).with_limits(config).code_stats(),
+            Activity::read(Path::new(path)),
+        )
+    });
+    let stats = stats?;
+    let analysis = Analysis {
+        languages: stats.languages.clone(),
+        partial: stats.partial,
+        activity,
+    };
+    if let Some(key) = key
+        && let Err(err) = write_cache(path, key, &analysis, &stats)
+    {
+        eprintln!("failed to write analysis cache for {}: {}", path, err);
+    }
+    Ok((analysis, stats))
+}
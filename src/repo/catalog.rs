@@ -0,0 +1,390 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use super::{GitProject, Repo, RepoState, git_dir, normalize_remote};
+
+const CATALOG_VERSION: u32 = 1;
+
+/// file formats of an exported catalog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatalogFormat {
+    Json,
+    Toml,
+    Csv,
+}
+
+impl CatalogFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "json" => Some(CatalogFormat::Json),
+            "toml" => Some(CatalogFormat::Toml),
+            "csv" => Some(CatalogFormat::Csv),
+            _ => None,
+        }
+    }
+
+    /// guess the format from a file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::parse(path.extension()?.to_str()?)
+    }
+}
+
+/// the user kept state of every repo, portable between machines
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Catalog {
+    pub version: u32,
+    pub repos: Vec<CatalogEntry>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CatalogEntry {
+    pub path: String,
+    /// used to find the repo when its path differs on the importing machine
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    pub aliases: Vec<String>,
+    pub archived: bool,
+    pub count: u32,
+    pub opens: Vec<u64>,
+}
+
+/// csv can't hold lists, aliases are joined with `;` and opens with spaces
+#[derive(Debug, Serialize, Deserialize)]
+struct CsvRow {
+    path: String,
+    remote: String,
+    name: String,
+    display_name: String,
+    aliases: String,
+    archived: bool,
+    count: u32,
+    opens: String,
+}
+
+impl From<&Repo> for CatalogEntry {
+    fn from(repo: &Repo) -> Self {
+        Self {
+            path: repo.path.clone(),
+            // members share the remote of their root, they are matched by path only
+            remote: repo.remote.clone().filter(|_| repo.parent.is_none()),
+            name: repo.name.clone(),
+            display_name: repo.display_name.clone(),
+            aliases: repo.aliases.clone(),
+            archived: repo.archived,
+            count: repo.count,
+            opens: repo.opens.clone(),
+        }
+    }
+}
+
+impl Catalog {
+    pub fn from_state(state: &RepoState) -> Self {
+        let repos = state.repos.read().unwrap();
+        let mut entries: Vec<CatalogEntry> = repos.iter().map(CatalogEntry::from).collect();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Self {
+            version: CATALOG_VERSION,
+            repos: entries,
+        }
+    }
+
+    pub fn write(&self, format: CatalogFormat) -> anyhow::Result<String> {
+        Ok(match format {
+            CatalogFormat::Json => serde_json::to_string_pretty(self)?,
+            CatalogFormat::Toml => toml::to_string(self)?,
+            CatalogFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(Vec::new());
+                for entry in &self.repos {
+                    writer.serialize(CsvRow {
+                        path: entry.path.clone(),
+                        remote: entry.remote.clone().unwrap_or_default(),
+                        name: entry.name.clone(),
+                        display_name: entry.display_name.clone().unwrap_or_default(),
+                        aliases: entry.aliases.join(";"),
+                        archived: entry.archived,
+                        count: entry.count,
+                        opens: entry
+                            .opens
+                            .iter()
+                            .map(u64::to_string)
+                            .collect::<Vec<_>>()
+                            .join(" "),
+                    })?;
+                }
+                String::from_utf8(writer.into_inner()?)?
+            }
+        })
+    }
+
+    pub fn read(content: &str, format: CatalogFormat) -> anyhow::Result<Self> {
+        let catalog: Catalog = match format {
+            CatalogFormat::Json => serde_json::from_str(content)?,
+            CatalogFormat::Toml => toml::from_str(content)?,
+            CatalogFormat::Csv => {
+                let mut reader = csv::Reader::from_reader(content.as_bytes());
+                let mut repos = Vec::new();
+                for row in reader.deserialize() {
+                    let row: CsvRow = row?;
+                    let opens = row
+                        .opens
+                        .split_whitespace()
+                        .map(str::parse)
+                        .collect::<Result<_, _>>()
+                        .with_context(|| format!("invalid opens for {}", row.path))?;
+                    repos.push(CatalogEntry {
+                        path: row.path,
+                        remote: Some(row.remote).filter(|remote| !remote.is_empty()),
+                        name: row.name,
+                        display_name: Some(row.display_name).filter(|name| !name.is_empty()),
+                        aliases: row
+                            .aliases
+                            .split(';')
+                            .filter(|alias| !alias.is_empty())
+                            .map(str::to_string)
+                            .collect(),
+                        archived: row.archived,
+                        count: row.count,
+                        opens,
+                    });
+                }
+                Catalog {
+                    version: CATALOG_VERSION,
+                    repos,
+                }
+            }
+        };
+
+        if catalog.version > CATALOG_VERSION {
+            anyhow::bail!(
+                "catalog version {} is newer than supported version {}",
+                catalog.version,
+                CATALOG_VERSION
+            );
+        }
+        Ok(catalog)
+    }
+}
+
+/// a `from=to` path prefix rule applied to imported paths
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathMap {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+impl PathMap {
+    pub fn parse(rule: &str) -> anyhow::Result<Self> {
+        let Some((from, to)) = rule.split_once('=') else {
+            anyhow::bail!("path map \"{rule}\" should look like /old/prefix=/new/prefix");
+        };
+        Ok(Self {
+            from: PathBuf::from(from),
+            to: PathBuf::from(to),
+        })
+    }
+
+    /// rewrite `path` with the longest matching rule
+    pub fn apply(maps: &[PathMap], path: &str) -> String {
+        maps.iter()
+            .filter_map(|map| {
+                let rest = Path::new(path).strip_prefix(&map.from).ok()?;
+                Some((map.from.components().count(), map.to.join(rest)))
+            })
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, path)| path.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub by_path: usize,
+    pub by_remote: usize,
+    /// found on disk but not indexed yet
+    pub added: usize,
+    /// paths of entries that matched nothing
+    pub skipped: Vec<String>,
+}
+
+impl RepoState {
+    /// merge a catalog into the state: paths are remapped first, then matched
+    /// against indexed repos, then by remote url. merging keeps the larger
+    /// open count and the union of aliases and opens, so importing twice
+    /// changes nothing
+    pub fn import(
+        &self,
+        catalog: Catalog,
+        maps: &[PathMap],
+        max_samples: usize,
+    ) -> anyhow::Result<ImportReport> {
+        let mut report = ImportReport::default();
+        self.transact(|repos| {
+            for entry in catalog.repos {
+                let path = PathMap::apply(maps, &entry.path);
+
+                let target = if repos.iter().any(|repo| repo.path == path) {
+                    report.by_path += 1;
+                    Some(path.clone())
+                } else if let Some(found) = entry
+                    .remote
+                    .as_deref()
+                    .and_then(|remote| find_by_remote(repos, remote))
+                {
+                    report.by_remote += 1;
+                    Some(found)
+                } else if git_dir(Path::new(&path)).is_some() {
+                    let full_path = PathBuf::from(&path);
+                    let folder_name = full_path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    repos.insert(Repo {
+                        imported: true,
+                        ..Repo::from(GitProject {
+                            full_path,
+                            folder_name,
+                        })
+                    });
+                    report.added += 1;
+                    Some(path.clone())
+                } else {
+                    report.skipped.push(entry.path.clone());
+                    None
+                };
+
+                let Some(target) = target else {
+                    continue;
+                };
+                let Some(mut repo) = repos.iter().find(|repo| repo.path == target).cloned() else {
+                    continue;
+                };
+                repo.merge_entry(entry, max_samples);
+                repos.replace(repo);
+            }
+        })?;
+        Ok(report)
+    }
+}
+
+/// the path of the only root repo with this remote, ambiguous remotes
+/// (e.g. several clones of one repo) match nothing
+fn find_by_remote(repos: &HashSet<Repo>, remote: &str) -> Option<String> {
+    let remote = normalize_remote(remote);
+    let mut found = repos.iter().filter(|repo| {
+        repo.parent.is_none()
+            && repo.remote.as_deref().map(normalize_remote) == Some(remote.clone())
+    });
+    let repo = found.next()?;
+    found.next().is_none().then(|| repo.path.clone())
+}
+
+impl Repo {
+    fn merge_entry(&mut self, entry: CatalogEntry, max_samples: usize) {
+        if entry.display_name.is_some() {
+            self.display_name = entry.display_name;
+        }
+        for alias in entry.aliases {
+            if !self.aliases.contains(&alias) {
+                self.aliases.push(alias);
+            }
+        }
+        self.archived |= entry.archived;
+        self.count = self.count.max(entry.count);
+
+        self.opens.extend(entry.opens);
+        self.opens.sort_unstable();
+        self.opens.dedup();
+        if self.opens.len() > max_samples {
+            let overflow = self.opens.len() - max_samples;
+            self.opens.drain(..overflow);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog() -> Catalog {
+        Catalog {
+            version: CATALOG_VERSION,
+            repos: vec![
+                CatalogEntry {
+                    path: "/home/me/code/api".to_string(),
+                    remote: Some("git@github.com:1password/api.git".to_string()),
+                    name: "api".to_string(),
+                    display_name: Some("Billing API".to_string()),
+                    aliases: vec!["bill".to_string(), "payments".to_string()],
+                    archived: false,
+                    count: 3,
+                    opens: vec![10, 20, 30],
+                },
+                CatalogEntry {
+                    path: "/home/me/old, \"quoted\"".to_string(),
+                    name: "old".to_string(),
+                    archived: true,
+                    ..Default::default()
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn round_trips_every_format() {
+        for format in [CatalogFormat::Json, CatalogFormat::Toml, CatalogFormat::Csv] {
+            let written = catalog().write(format).unwrap();
+            let read = Catalog::read(&written, format).unwrap();
+            assert_eq!(read.version, CATALOG_VERSION);
+            assert_eq!(read.repos.len(), 2, "{format:?}");
+            for (read, expected) in read.repos.iter().zip(catalog().repos) {
+                assert_eq!(read.path, expected.path, "{format:?}");
+                assert_eq!(read.remote, expected.remote, "{format:?}");
+                assert_eq!(read.name, expected.name, "{format:?}");
+                assert_eq!(read.display_name, expected.display_name, "{format:?}");
+                assert_eq!(read.aliases, expected.aliases, "{format:?}");
+                assert_eq!(read.archived, expected.archived, "{format:?}");
+                assert_eq!(read.count, expected.count, "{format:?}");
+                assert_eq!(read.opens, expected.opens, "{format:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn newer_catalogs_are_refused() {
+        let written = Catalog {
+            version: CATALOG_VERSION + 1,
+            repos: Vec::new(),
+        }
+        .write(CatalogFormat::Json)
+        .unwrap();
+        assert!(Catalog::read(&written, CatalogFormat::Json).is_err());
+    }
+
+    #[test]
+    fn path_maps_use_the_longest_prefix() {
+        let maps = [
+            PathMap::parse("/Users/old=/home/new").unwrap(),
+            PathMap::parse("/Users/old/work=/work").unwrap(),
+        ];
+        for (path, expected) in [
+            ("/Users/old/code/api", "/home/new/code/api"),
+            ("/Users/old/work/api", "/work/api"),
+            // prefixes match whole components only
+            ("/Users/older/api", "/Users/older/api"),
+            ("/srv/api", "/srv/api"),
+        ] {
+            assert_eq!(PathMap::apply(&maps, path), expected);
+        }
+        assert!(PathMap::parse("/no/separator").is_err());
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::LanguageStat;
+
+/// how many files the largest files table keeps
+const LARGEST_FILES: usize = 10;
+
+/// lines of one analysed file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStat {
+    /// relative to the repo, `/` separated
+    pub path: String,
+    pub language: String,
+    pub code: u32,
+    pub comments: u32,
+    pub blanks: u32,
+}
+
+impl FileStat {
+    pub fn lines(&self) -> u32 {
+        self.code + self.comments + self.blanks
+    }
+}
+
+/// languages of one top-level directory, `.` holds the files in the root
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirectoryStat {
+    pub name: String,
+    pub languages: Vec<LanguageStat>,
+}
+
+/// everything `LanguageAnalyzer` knows about a repo, for the detail pane and
+/// `git_launcher code-stats`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CodeStats {
+    pub languages: Vec<LanguageStat>,
+    pub largest_files: Vec<FileStat>,
+    pub directories: Vec<DirectoryStat>,
+    /// a limit was hit and only a sample of the files was counted
+    pub partial: bool,
+}
+
+/// file formats of an exported `CodeStats`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsFormat {
+    Json,
+    Markdown,
+}
+
+impl StatsFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "json" => Some(StatsFormat::Json),
+            "markdown" | "md" => Some(StatsFormat::Markdown),
+            _ => None,
+        }
+    }
+}
+
+impl CodeStats {
+    pub fn from_files(files: Vec<FileStat>) -> Self {
+        let mut directories: BTreeMap<&str, Vec<&FileStat>> = BTreeMap::new();
+        for file in &files {
+            let directory = match file.path.split_once('/') {
+                Some((directory, _)) => directory,
+                None => ".",
+            };
+            directories.entry(directory).or_default().push(file);
+        }
+        let directories = directories
+            .into_iter()
+            .map(|(name, files)| DirectoryStat {
+                name: name.to_string(),
+                languages: summarize(files),
+            })
+            .collect();
+
+        let languages = summarize(&files);
+        let mut largest_files = files;
+        largest_files.sort_by(|a, b| {
+            b.code
+                .cmp(&a.code)
+                .then_with(|| b.lines().cmp(&a.lines()))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        largest_files.truncate(LARGEST_FILES);
+
+        Self {
+            languages,
+            largest_files,
+            directories,
+            partial: false,
+        }
+    }
+
+    /// lines and files over all languages
+    pub fn total(&self) -> LanguageStat {
+        self.languages.iter().fold(
+            LanguageStat {
+                name: "Total".to_string(),
+                code: 0,
+                comments: 0,
+                blanks: 0,
+                files: 0,
+            },
+            |total, stat| LanguageStat {
+                code: total.code + stat.code,
+                comments: total.comments + stat.comments,
+                blanks: total.blanks + stat.blanks,
+                files: total.files + stat.files,
+                ..total
+            },
+        )
+    }
+
+    pub fn write(&self, format: StatsFormat, title: &str) -> anyhow::Result<String> {
+        Ok(match format {
+            StatsFormat::Json => serde_json::to_string_pretty(self)?,
+            StatsFormat::Markdown => self.markdown(title),
+        })
+    }
+
+    fn markdown(&self, title: &str) -> String {
+        let mut out = format!("# {title}\n\n");
+        if self.partial {
+            out.push_str(
+                "Counted from a sample of the files, the repo exceeds the analysis limits.\n\n",
+            );
+        }
+        out.push_str("## Languages\n\n");
+        out.push_str("| Language | Files | Code | Comments | Blanks |\n");
+        out.push_str("| --- | ---: | ---: | ---: | ---: |\n");
+        for stat in self.languages.iter().chain([&self.total()]) {
+            out.push_str(&format!(
+                "| {} | {} | {} | {} | {} |\n",
+                stat.name, stat.files, stat.code, stat.comments, stat.blanks
+            ));
+        }
+
+        out.push_str("\n## Largest files\n\n");
+        out.push_str("| File | Language | Code | Comments | Blanks |\n");
+        out.push_str("| --- | --- | ---: | ---: | ---: |\n");
+        for file in &self.largest_files {
+            out.push_str(&format!(
+                "| {} | {} | {} | {} | {} |\n",
+                escape(&file.path),
+                file.language,
+                file.code,
+                file.comments,
+                file.blanks
+            ));
+        }
+
+        out.push_str("\n## Directories\n\n");
+        out.push_str("| Directory | Language | Files | Code | Comments | Blanks |\n");
+        out.push_str("| --- | --- | ---: | ---: | ---: | ---: |\n");
+        for directory in &self.directories {
+            for stat in &directory.languages {
+                out.push_str(&format!(
+                    "| {} | {} | {} | {} | {} | {} |\n",
+                    escape(&directory.name),
+                    stat.name,
+                    stat.files,
+                    stat.code,
+                    stat.comments,
+                    stat.blanks
+                ));
+            }
+        }
+        out
+    }
+}
+
+/// per language totals of `files`, the largest by lines of code first
+pub fn summarize<'a>(files: impl IntoIterator<Item = &'a FileStat>) -> Vec<LanguageStat> {
+    let mut languages: BTreeMap<&str, LanguageStat> = BTreeMap::new();
+    for file in files {
+        let stat = languages
+            .entry(file.language.as_str())
+            .or_insert_with(|| LanguageStat {
+                name: file.language.clone(),
+                code: 0,
+                comments: 0,
+                blanks: 0,
+                files: 0,
+            });
+        stat.code += file.code;
+        stat.comments += file.comments;
+        stat.blanks += file.blanks;
+        stat.files += 1;
+    }
+
+    let mut stats: Vec<LanguageStat> = languages.into_values().collect();
+    stats.sort_by(|a, b| b.code.cmp(&a.code).then_with(|| a.name.cmp(&b.name)));
+    stats
+}
+
+/// file names may contain `|`, which would split a table cell
+fn escape(text: &str) -> String {
+    text.replace('|', "\\|")
+}
@@ -0,0 +1,261 @@
+use std::{
+    collections::HashMap,
+    fs::{self, Metadata},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use futures::{StreamExt, stream::FuturesUnordered};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+
+use super::{Repo, RepoState, now_secs};
+
+/// sizes older than this are measured again
+const MAX_AGE_SECS: u64 = 24 * 60 * 60;
+/// measured sizes are stored in batches of this many repos
+const FLUSH_BATCH: usize = 16;
+/// or once this long passed since the last batch
+const FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// bytes on disk of a repo, split into source files, `.git` and build
+/// artifacts (directories named in `ignore_dirs`, like `target` or `node_modules`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiskUsage {
+    pub worktree: u64,
+    pub git: u64,
+    pub artifacts: u64,
+    /// unix seconds of the measurement
+    pub measured_at: u64,
+}
+
+impl DiskUsage {
+    pub fn total(&self) -> u64 {
+        self.worktree + self.git + self.artifacts
+    }
+
+    /// walk `path` without following symlinks, `artifact_dirs` are directory
+    /// names counted as build output wherever they appear
+    pub fn measure(path: &Path, artifact_dirs: &[String]) -> Self {
+        let mut usage = DiskUsage {
+            git: dir_size(&path.join(".git")),
+            measured_at: now_secs(),
+            ..Default::default()
+        };
+
+        let mut stack = vec![path.to_path_buf()];
+        while let Some(dir) = stack.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(meta) = entry.metadata() else {
+                    continue;
+                };
+                if !meta.is_dir() {
+                    usage.worktree += allocated(&meta);
+                    continue;
+                }
+
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                if dir == path && name == ".git" {
+                    continue;
+                }
+                if artifact_dirs.iter().any(|dir| *dir == name) {
+                    usage.artifacts += dir_size(&entry.path());
+                } else {
+                    stack.push(entry.path());
+                }
+            }
+        }
+
+        usage
+    }
+
+    fn is_stale(usage: Option<&DiskUsage>, now: u64) -> bool {
+        usage.is_none_or(|usage| now.saturating_sub(usage.measured_at) > MAX_AGE_SECS)
+    }
+
+    /// sum of all repos, workspace members are skipped as their root already
+    /// contains them
+    pub fn sum<'a>(repos: impl IntoIterator<Item = &'a Repo>) -> Self {
+        repos
+            .into_iter()
+            .filter(|repo| repo.parent.is_none())
+            .filter_map(|repo| repo.disk_usage.as_ref())
+            .fold(DiskUsage::default(), |sum, usage| DiskUsage {
+                worktree: sum.worktree + usage.worktree,
+                git: sum.git + usage.git,
+                artifacts: sum.artifacts + usage.artifacts,
+                measured_at: sum.measured_at.max(usage.measured_at),
+            })
+    }
+}
+
+fn dir_size(path: &Path) -> u64 {
+    let mut size = 0;
+    let mut stack = vec![path.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if meta.is_dir() {
+                stack.push(entry.path());
+            } else {
+                size += allocated(&meta);
+            }
+        }
+    }
+    size
+}
+
+/// space taken on disk, sparse files and small files are counted by blocks
+#[cfg(unix)]
+fn allocated(meta: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.blocks() * 512
+}
+
+#[cfg(not(unix))]
+fn allocated(meta: &Metadata) -> u64 {
+    meta.len()
+}
+
+/// format bytes like `1.2 GB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024. && unit < UNITS.len() - 1 {
+        size /= 1024.;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// measure every repo whose size is missing or older than a day (all of them
+/// with `force`) and store the results as they come in, members are skipped
+pub async fn measure_disk_usage(
+    state: &RepoState,
+    artifact_dirs: &[String],
+    max_concurrent_tasks: usize,
+    force: bool,
+) -> anyhow::Result<()> {
+    let now = now_secs();
+    let paths: Vec<PathBuf> = state
+        .repos
+        .read()
+        .unwrap()
+        .iter()
+        .filter(|repo| repo.parent.is_none() && !repo.unavailable)
+        .filter(|repo| force || DiskUsage::is_stale(repo.disk_usage.as_ref(), now))
+        .map(|repo| PathBuf::from(&repo.path))
+        .collect();
+    if paths.is_empty() {
+        return Ok(());
+    }
+
+    let semaphore = Arc::new(Semaphore::new(max_concurrent_tasks.max(1)));
+    let artifact_dirs: Arc<Vec<String>> = Arc::new(
+        artifact_dirs
+            .iter()
+            .filter(|dir| *dir != ".git")
+            .cloned()
+            .collect(),
+    );
+
+    let mut tasks: FuturesUnordered<_> = paths
+        .into_iter()
+        .map(|path| {
+            let semaphore = semaphore.clone();
+            let artifact_dirs = artifact_dirs.clone();
+            tokio::spawn(async move {
+                let _permit = semaphore.acquire_owned().await?;
+                let usage = tokio::task::spawn_blocking(move || {
+                    let usage = DiskUsage::measure(&path, &artifact_dirs);
+                    (path.to_string_lossy().to_string(), usage)
+                })
+                .await?;
+                Ok::<_, anyhow::Error>(usage)
+            })
+        })
+        .collect();
+
+    // a large tree can take minutes, sizes measured so far show up meanwhile
+    let mut measured = HashMap::new();
+    let mut flushed_at = Instant::now();
+    while let Some(result) = tasks.next().await {
+        let (path, usage) = result??;
+        measured.insert(path, usage);
+        if measured.len() >= FLUSH_BATCH || flushed_at.elapsed() >= FLUSH_INTERVAL {
+            store_usage(state, &mut measured)?;
+            flushed_at = Instant::now();
+        }
+    }
+    store_usage(state, &mut measured)
+}
+
+fn store_usage(state: &RepoState, measured: &mut HashMap<String, DiskUsage>) -> anyhow::Result<()> {
+    if measured.is_empty() {
+        return Ok(());
+    }
+    state.update_all(|repo| {
+        if let Some(usage) = measured.remove(&repo.path) {
+            repo.disk_usage = Some(usage);
+        }
+    })?;
+    // repos removed while measuring
+    measured.clear();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, bytes: usize) -> u64 {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![b'x'; bytes]).unwrap();
+        allocated(&fs::metadata(path).unwrap())
+    }
+
+    #[test]
+    fn measures_worktree_git_and_artifacts() {
+        let repo = tempfile::tempdir().unwrap();
+        let root = repo.path();
+        let worktree = write(&root.join("src/main.rs"), 10_000)
+            + write(&root.join("README.md"), 100)
+            // `.git` is only special at the top
+            + write(&root.join("vendor/.git/HEAD"), 100);
+        let git = write(&root.join(".git/objects/pack/a.pack"), 50_000)
+            + write(&root.join(".git/HEAD"), 20);
+        let artifacts = write(&root.join("target/debug/app"), 200_000)
+            + write(&root.join("web/node_modules/a/index.js"), 3_000);
+
+        let outside = tempfile::tempdir().unwrap();
+        write(&outside.path().join("big"), 500_000);
+        #[cfg(unix)]
+        let worktree = {
+            let link = root.join("linked");
+            std::os::unix::fs::symlink(outside.path(), &link).unwrap();
+            worktree + allocated(&fs::symlink_metadata(&link).unwrap())
+        };
+
+        let usage = DiskUsage::measure(root, &["target".to_string(), "node_modules".to_string()]);
+        assert_eq!(
+            (usage.worktree, usage.git, usage.artifacts),
+            (worktree, git, artifacts)
+        );
+        assert_eq!(usage.total(), worktree + git + artifacts);
+    }
+}
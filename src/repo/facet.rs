@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use super::{Query, Repo, SIGNIFICANT_SHARE, normalize_language, normalize_remote};
+
+/// how many values of each kind the facet bar shows
+const MAX_VALUES: usize = 5;
+
+/// what a facet filters on, in the order the facet bar shows them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FacetKind {
+    Language,
+    ProjectType,
+    Host,
+    Tag,
+}
+
+impl FacetKind {
+    /// the filter key in the search box
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            FacetKind::Language => "lang",
+            FacetKind::ProjectType => "type",
+            FacetKind::Host => "host",
+            FacetKind::Tag => "tag",
+        }
+    }
+
+    /// `(filter value, label)` of every value `repo` has
+    fn values(&self, repo: &Repo) -> Vec<(String, String)> {
+        match self {
+            FacetKind::Language => repo
+                .significant_languages()
+                .into_iter()
+                .map(|language| (normalize_language(language), language.to_string()))
+                .collect(),
+            FacetKind::ProjectType => repo
+                .project_types
+                .iter()
+                .map(|kind| (kind.key().to_string(), kind.label().to_string()))
+                .collect(),
+            FacetKind::Host => repo
+                .host()
+                .map(|host| (host.clone(), host))
+                .into_iter()
+                .collect(),
+            FacetKind::Tag => repo
+                .tags()
+                .iter()
+                .map(|tag| (tag.to_lowercase(), tag.clone()))
+                .collect(),
+        }
+    }
+
+    /// the filter values of this kind in `query`
+    fn selected<'a>(&self, query: &'a Query) -> &'a [String] {
+        match self {
+            FacetKind::Language => &query.languages,
+            FacetKind::ProjectType => &query.project_types,
+            FacetKind::Host => &query.hosts,
+            FacetKind::Tag => &query.tags,
+        }
+    }
+}
+
+/// one value of a facet with the number of results that have it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Facet {
+    pub kind: FacetKind,
+    /// as written in the filter, e.g. `cpp` for C++
+    pub value: String,
+    pub label: String,
+    pub count: usize,
+}
+
+impl Facet {
+    /// the `key:value` filter that narrows the results to this facet
+    pub fn token(&self) -> String {
+        format!("{}:{}", self.kind.prefix(), self.value)
+    }
+
+    /// whether the query already filters on this facet
+    pub fn is_selected(&self, query: &Query) -> bool {
+        self.kind
+            .selected(query)
+            .iter()
+            .any(|selected| self.has_value(selected))
+    }
+
+    /// `search` with this facet's filter added, or removed when it is there
+    pub fn toggle(&self, search: &str) -> String {
+        if !self.is_selected(&Query::parse(search)) {
+            return format!("{} {}", search.trim_end(), self.token())
+                .trim_start()
+                .to_string();
+        }
+        search
+            .split_whitespace()
+            .filter(|token| {
+                !token
+                    .split_once(':')
+                    .is_some_and(|(key, value)| key == self.kind.prefix() && self.has_value(value))
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn has_value(&self, value: &str) -> bool {
+        match self.kind {
+            FacetKind::Language => normalize_language(value) == self.value,
+            _ => value.to_lowercase() == self.value,
+        }
+    }
+
+    /// counts of every language, project type, host and tag in `repos`, the
+    /// most common values of each kind first. a repo with two languages
+    /// counts for both, the same way `lang:` matches it
+    pub fn count(repos: &[Repo]) -> Vec<Facet> {
+        let mut facets = Vec::new();
+        for kind in [
+            FacetKind::Language,
+            FacetKind::ProjectType,
+            FacetKind::Host,
+            FacetKind::Tag,
+        ] {
+            let mut counts: HashMap<String, (String, usize)> = HashMap::new();
+            for repo in repos {
+                for (value, label) in kind.values(repo) {
+                    // the search box splits filters at whitespace
+                    if value.is_empty() || value.contains(char::is_whitespace) {
+                        continue;
+                    }
+                    counts.entry(value).or_insert((label, 0)).1 += 1;
+                }
+            }
+
+            let mut values: Vec<Facet> = counts
+                .into_iter()
+                .map(|(value, (label, count))| Facet {
+                    kind,
+                    value,
+                    label,
+                    count,
+                })
+                .collect();
+            values.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.label.cmp(&b.label)));
+            values.truncate(MAX_VALUES);
+            facets.extend(values);
+        }
+        facets
+    }
+}
+
+impl Repo {
+    /// languages that are a significant share of the code, the top language
+    /// for repos analysed before breakdowns were kept
+    pub fn significant_languages(&self) -> Vec<&str> {
+        if self.languages.is_empty() {
+            return Some(self.language.as_str())
+                .filter(|language| *language != "unknown")
+                .into_iter()
+                .collect();
+        }
+        self.languages
+            .iter()
+            .filter(|stat| self.language_share(&stat.name) >= SIGNIFICANT_SHARE)
+            .map(|stat| stat.name.as_str())
+            .collect()
+    }
+
+    /// host of the remote, like `github.com`
+    pub fn host(&self) -> Option<String> {
+        let remote = normalize_remote(self.remote.as_deref()?);
+        let host = remote.split('/').next()?;
+        (!host.is_empty()).then(|| host.to_string())
+    }
+}
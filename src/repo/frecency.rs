@@ -0,0 +1,123 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::GitLauncherRankingConfig;
+
+use super::Repo;
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+impl Repo {
+    /// record an open at `now`, keeping only the latest `max_samples` timestamps
+    pub fn record_open(&mut self, now: u64, max_samples: usize) {
+        self.count += 1;
+        self.opens.push(now);
+        if self.opens.len() > max_samples {
+            let overflow = self.opens.len() - max_samples;
+            self.opens.drain(..overflow);
+        }
+    }
+
+    /// frequency decayed by recency, like firefox's frecency: every sampled
+    /// open decays exponentially with its age and the sum is scaled up to the
+    /// total open count
+    pub fn frecency(&self, now: u64, config: &GitLauncherRankingConfig) -> f64 {
+        if self.opens.is_empty() {
+            return 0.0;
+        }
+
+        let half_life = (config.half_life_hours * 3600.0).max(1.0);
+        let decayed: f64 = self
+            .opens
+            .iter()
+            .map(|&ts| {
+                let age = now.saturating_sub(ts) as f64;
+                (-age / half_life).exp2()
+            })
+            .sum();
+
+        let total = self.count.max(self.opens.len() as u32) as f64;
+        decayed * total / self.opens.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo::{Query, search_repos, test_repo};
+
+    const HOUR: u64 = 3600;
+    const NOW: u64 = 1_000 * HOUR;
+
+    fn ranking(half_life_hours: f64) -> GitLauncherRankingConfig {
+        GitLauncherRankingConfig {
+            half_life_hours,
+            ..Default::default()
+        }
+    }
+
+    fn opened(path: &str, opens: &[u64]) -> Repo {
+        let mut repo = test_repo(path);
+        for open in opens {
+            repo.record_open(*open, 10);
+        }
+        repo
+    }
+
+    #[test]
+    fn record_open_keeps_the_latest_samples() {
+        let mut repo = test_repo("/a");
+        for now in 1..=5 {
+            repo.record_open(now, 3);
+        }
+        assert_eq!(repo.count, 5);
+        assert_eq!(repo.opens, [3, 4, 5]);
+    }
+
+    #[test]
+    fn opens_decay_by_half_life() {
+        let ranking = ranking(1.0);
+        assert_eq!(test_repo("/a").frecency(NOW, &ranking), 0.0);
+        assert_eq!(opened("/a", &[NOW]).frecency(NOW, &ranking), 1.0);
+        assert_eq!(opened("/a", &[NOW - HOUR]).frecency(NOW, &ranking), 0.5);
+        assert_eq!(
+            opened("/a", &[NOW - 2 * HOUR, NOW]).frecency(NOW, &ranking),
+            1.25
+        );
+        // opens in the future count as fresh
+        assert_eq!(opened("/a", &[NOW + HOUR]).frecency(NOW, &ranking), 1.0);
+    }
+
+    #[test]
+    fn dropped_samples_still_count() {
+        let ranking = ranking(1.0);
+        let mut repo = opened("/a", &[NOW, NOW]);
+        repo.count = 4;
+        // the two samples stand in for all four opens
+        assert_eq!(repo.frecency(NOW, &ranking), 4.0);
+    }
+
+    #[test]
+    fn recent_opens_outrank_old_ones_and_ties_go_by_title() {
+        let ranking = ranking(24.0);
+        let now = now_secs();
+        let repos = [
+            opened("/often", &[now - 30 * 24 * HOUR; 5]),
+            opened("/lately", &[now - HOUR]),
+            opened("/b", &[]),
+            opened("/a", &[]),
+        ]
+        .into_iter()
+        .collect();
+
+        let order: Vec<String> = search_repos(&repos, &Query::parse(""), &ranking)
+            .into_iter()
+            .map(|repo| repo.path)
+            .collect();
+        assert_eq!(order, ["/lately", "/often", "/a", "/b"]);
+    }
+}
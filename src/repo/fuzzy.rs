@@ -0,0 +1,216 @@
+use std::ops::Range;
+
+use super::Repo;
+
+const SCORE_MATCH: i64 = 16;
+const PENALTY_GAP_START: i64 = 3;
+const PENALTY_GAP_EXTENSION: i64 = 1;
+/// first character of the candidate
+const BONUS_START: i64 = 10;
+/// after `/`, the start of a path component
+const BONUS_PATH: i64 = 9;
+/// after `-`, `_`, `.` or a space
+const BONUS_BOUNDARY: i64 = 8;
+/// an uppercase letter after a lowercase one, or a digit after a letter
+const BONUS_CAMEL: i64 = 7;
+/// least bonus of a character that directly follows the previous match, it
+/// also keeps the bonus of the first character of its run
+const BONUS_CONSECUTIVE: i64 = 4;
+/// the pattern character has the same case as the candidate
+const BONUS_CASE: i64 = 1;
+/// path matches rank below name matches of the same quality
+const PENALTY_PATH: i64 = 8;
+
+/// a fuzzy match of a pattern in a candidate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// char indices of the matched characters in the candidate
+    pub positions: Vec<usize>,
+}
+
+impl FuzzyMatch {
+    /// byte ranges of the matched characters of `candidate`, neighbours merged
+    pub fn ranges(&self, candidate: &str) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        let mut positions = self.positions.iter().peekable();
+        for (index, (start, c)) in candidate.char_indices().enumerate() {
+            if positions.next_if(|position| **position == index).is_none() {
+                continue;
+            }
+            let end = start + c.len_utf8();
+            match ranges.last_mut() {
+                Some(last) if last.end == start => last.end = end,
+                _ => ranges.push(start..end),
+            }
+        }
+        ranges
+    }
+}
+
+fn bonus(prev: Option<char>, c: char) -> i64 {
+    match prev {
+        None => BONUS_START,
+        Some('/' | '\\') => BONUS_PATH,
+        Some('-' | '_' | '.' | ' ') => BONUS_BOUNDARY,
+        Some(prev) if prev.is_lowercase() && c.is_uppercase() => BONUS_CAMEL,
+        Some(prev) if prev.is_alphabetic() && c.is_numeric() => BONUS_CAMEL,
+        _ => 0,
+    }
+}
+
+fn eq_ignore_case(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// match `pattern` as a case-insensitive subsequence of `candidate` and
+/// score the best alignment: matches at word starts, camelCase humps and path
+/// components score higher, gaps between matches cost. whitespace in the
+/// pattern is ignored, so `git launcher` matches `git-launcher`
+pub fn fuzzy_match(pattern: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let pattern: Vec<char> = pattern.chars().filter(|c| !c.is_whitespace()).collect();
+    if pattern.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+    let chars: Vec<char> = candidate.chars().collect();
+    let (n, m) = (pattern.len(), chars.len());
+    if n > m {
+        return None;
+    }
+    let bonuses: Vec<i64> = (0..m)
+        .map(|j| bonus(j.checked_sub(1).map(|prev| chars[prev]), chars[j]))
+        .collect();
+
+    // score[i][j]: best score of pattern[..=i] with pattern[i] matched at
+    // chars[j], from[i][j] the position of pattern[i - 1] in that alignment
+    // and run[i][j] the bonus of the first match of its consecutive run
+    let mut score = vec![vec![None::<i64>; m]; n];
+    let mut from = vec![vec![0; m]; n];
+    let mut run = vec![vec![0; m]; n];
+    for i in 0..n {
+        // best alignment of pattern[..i] ending at least two chars before j,
+        // with the gap penalty up to j already taken off
+        let mut gapped: Option<(i64, usize)> = None;
+        for j in i..m {
+            if i > 0 && j >= 2 {
+                let candidate = score[i - 1][j - 2].map(|s| (s - PENALTY_GAP_START, j - 2));
+                gapped = match (gapped, candidate) {
+                    (Some((g, k)), Some((c, l))) => Some(if c >= g - PENALTY_GAP_EXTENSION {
+                        (c, l)
+                    } else {
+                        (g - PENALTY_GAP_EXTENSION, k)
+                    }),
+                    (Some((g, k)), None) => Some((g - PENALTY_GAP_EXTENSION, k)),
+                    (None, candidate) => candidate,
+                };
+            }
+            if !eq_ignore_case(pattern[i], chars[j]) {
+                continue;
+            }
+
+            let case = if pattern[i] == chars[j] {
+                BONUS_CASE
+            } else {
+                0
+            };
+            if i == 0 {
+                score[i][j] = Some(SCORE_MATCH + bonuses[j] + case);
+                run[i][j] = bonuses[j];
+                continue;
+            }
+
+            let consecutive = j.checked_sub(1).and_then(|k| {
+                let bonus = bonuses[j].max(run[i - 1][k]).max(BONUS_CONSECUTIVE);
+                score[i - 1][k].map(|s| (s + bonus, k, run[i - 1][k].max(bonuses[j])))
+            });
+            let gapped = gapped.map(|(s, k)| (s + bonuses[j], k, bonuses[j]));
+            let best = match (consecutive, gapped) {
+                (Some(c), Some(g)) => Some(if c.0 >= g.0 { c } else { g }),
+                (c, g) => c.or(g),
+            };
+            if let Some((s, k, first)) = best {
+                score[i][j] = Some(s + SCORE_MATCH + case);
+                from[i][j] = k;
+                run[i][j] = first;
+            }
+        }
+    }
+
+    let (mut j, best) = score[n - 1]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, s)| s.map(|s| (j, s)))
+        .max_by_key(|(j, s)| (*s, std::cmp::Reverse(*j)))?;
+    let mut positions = vec![j];
+    for i in (1..n).rev() {
+        j = from[i][j];
+        positions.push(j);
+    }
+    positions.reverse();
+
+    Some(FuzzyMatch {
+        score: best,
+        positions,
+    })
+}
+
+impl Repo {
+    /// best fuzzy score over the names and the path, `None` if none matches
+    pub fn fuzzy_score(&self, pattern: &str) -> Option<i64> {
+        let names = self
+            .names()
+            .filter_map(|name| fuzzy_match(pattern, name))
+            .map(|found| found.score);
+        let path = fuzzy_match(pattern, &self.path).map(|found| found.score - PENALTY_PATH);
+        names.chain(path).max()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(pattern: &str, candidate: &str) -> i64 {
+        fuzzy_match(pattern, candidate).unwrap().score
+    }
+
+    #[test]
+    fn subsequence_ignores_case() {
+        assert_eq!(fuzzy_match("gl", "git-launcher").unwrap().positions, [0, 4]);
+        assert_eq!(fuzzy_match("GL", "git-launcher").unwrap().positions, [0, 4]);
+        assert_eq!(
+            fuzzy_match("git launcher", "git-launcher")
+                .unwrap()
+                .positions
+                .len(),
+            11
+        );
+        assert!(fuzzy_match("lg", "git-launcher").is_none());
+        assert!(fuzzy_match("gitx", "git").is_none());
+    }
+
+    #[test]
+    fn prefers_boundaries() {
+        // the `l` of `lib` rather than the one inside `golang`
+        assert_eq!(fuzzy_match("gl", "golang-lib").unwrap().positions, [0, 7]);
+        assert!(score("gl", "git-launcher") > score("gl", "goal"));
+        assert!(score("ab", "fooApiBar") > score("ab", "fooapibar"));
+        assert!(score("ws", "code/web/server") > score("ws", "code/jewels"));
+    }
+
+    #[test]
+    fn prefers_consecutive_and_early_matches() {
+        assert!(score("api", "billing-api") > score("api", "a-pig-island"));
+        assert!(score("git", "git-launcher") > score("git", "my-git"));
+    }
+
+    #[test]
+    fn ranges_merge_neighbours() {
+        let candidate = "git-läuncher";
+        let found = fuzzy_match("gitlä", candidate).unwrap();
+        assert_eq!(found.ranges(candidate), [0..3, 4..7]);
+    }
+}
@@ -0,0 +1,335 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{GitLauncherHistoryConfig, HISTORY_PATH},
+    state_file::{FileStamp, StateLock},
+};
+
+/// how a repo launch was triggered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trigger {
+    Click,
+    Keyboard,
+    Cli,
+}
+
+impl Trigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Trigger::Click => "click",
+            Trigger::Keyboard => "keyboard",
+            Trigger::Cli => "cli",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub timestamp: u64,
+    pub name: String,
+    pub path: String,
+    pub editor: String,
+    pub trigger: Trigger,
+}
+
+/// append-only launch log stored as json lines, rotated by size
+/// (`history.jsonl`, `history.jsonl.1`, ... with `.1` being the newest rotation)
+pub struct History {
+    path: PathBuf,
+    config: GitLauncherHistoryConfig,
+    recent: Mutex<Option<RecentCache>>,
+}
+
+/// the last `recent` list with its limit and the stamp of the file it was
+/// read from
+struct RecentCache {
+    stamp: Option<FileStamp>,
+    limit: usize,
+    entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    pub fn new(config: GitLauncherHistoryConfig) -> Self {
+        Self::with_path(HISTORY_PATH.clone(), config)
+    }
+
+    pub fn with_path<P: Into<PathBuf>>(path: P, config: GitLauncherHistoryConfig) -> Self {
+        Self {
+            path: path.into(),
+            config,
+            recent: Mutex::new(None),
+        }
+    }
+
+    pub fn append(&self, entry: &HistoryEntry) -> anyhow::Result<()> {
+        let _lock = StateLock::exclusive(&self.path)?;
+        self.rotate_if_needed()?;
+
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{index}"));
+        PathBuf::from(name)
+    }
+
+    fn rotate_if_needed(&self) -> anyhow::Result<()> {
+        let size = match fs::metadata(&self.path) {
+            Ok(metadata) => metadata.len(),
+            Err(_) => return Ok(()),
+        };
+        if size < self.config.max_file_size {
+            return Ok(());
+        }
+
+        if self.config.max_files == 0 {
+            fs::remove_file(&self.path)?;
+            return Ok(());
+        }
+
+        let oldest = self.rotated_path(self.config.max_files);
+        if oldest.exists() {
+            fs::remove_file(&oldest)?;
+        }
+        for index in (1..self.config.max_files).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated_path(1))?;
+        Ok(())
+    }
+
+    fn read_file(path: &Path) -> Vec<HistoryEntry> {
+        let Ok(content) = fs::read_to_string(path) else {
+            return Vec::new();
+        };
+        // skip lines we can't parse, e.g. a line cut short by a crash
+        content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()
+    }
+
+    /// all entries, oldest first
+    pub fn entries(&self) -> Vec<HistoryEntry> {
+        let Ok(_lock) = StateLock::shared(&self.path) else {
+            return Vec::new();
+        };
+        let mut entries = Vec::new();
+        for index in (1..=self.config.max_files).rev() {
+            entries.extend(Self::read_file(&self.rotated_path(index)));
+        }
+        entries.extend(Self::read_file(&self.path));
+        entries
+    }
+
+    /// the last `limit` opened repos, newest first, one entry per repo. the
+    /// files are only read again once an append or rotation changed
+    /// `history.jsonl`, `:recent` asks on every keystroke
+    pub fn recent(&self, limit: usize) -> Vec<HistoryEntry> {
+        let stamp = FileStamp::of(&self.path);
+        let mut cached = self.recent.lock().unwrap();
+        if let Some(cached) = &*cached
+            && cached.stamp == stamp
+            && cached.limit == limit
+        {
+            return cached.entries.clone();
+        }
+
+        let mut seen = HashSet::new();
+        let recent: Vec<HistoryEntry> = self
+            .entries()
+            .into_iter()
+            .rev()
+            .filter(|entry| seen.insert(entry.path.clone()))
+            .take(limit)
+            .collect();
+        *cached = Some(RecentCache {
+            stamp,
+            limit,
+            entries: recent.clone(),
+        });
+        recent
+    }
+
+    pub fn stats(&self) -> UsageStats {
+        UsageStats::from_entries(&self.entries())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UsageStats {
+    pub total: usize,
+    pub first: Option<u64>,
+    pub last: Option<u64>,
+    /// (repo path, opens), most opened first
+    pub by_repo: Vec<(String, usize)>,
+    pub by_editor: Vec<(String, usize)>,
+    pub by_trigger: Vec<(String, usize)>,
+    /// opens per hour of day (utc)
+    pub by_hour: [usize; 24],
+}
+
+impl UsageStats {
+    pub fn from_entries(entries: &[HistoryEntry]) -> Self {
+        let mut by_repo: HashMap<String, usize> = HashMap::new();
+        let mut by_editor: HashMap<String, usize> = HashMap::new();
+        let mut by_trigger: HashMap<String, usize> = HashMap::new();
+        let mut by_hour = [0; 24];
+
+        for entry in entries {
+            *by_repo.entry(entry.path.clone()).or_default() += 1;
+            *by_editor.entry(entry.editor.clone()).or_default() += 1;
+            *by_trigger
+                .entry(entry.trigger.as_str().to_string())
+                .or_default() += 1;
+            by_hour[((entry.timestamp / 3600) % 24) as usize] += 1;
+        }
+
+        Self {
+            total: entries.len(),
+            first: entries.iter().map(|e| e.timestamp).min(),
+            last: entries.iter().map(|e| e.timestamp).max(),
+            by_repo: sorted_counts(by_repo),
+            by_editor: sorted_counts(by_editor),
+            by_trigger: sorted_counts(by_trigger),
+            by_hour,
+        }
+    }
+}
+
+fn sorted_counts(counts: HashMap<String, usize>) -> Vec<(String, usize)> {
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(timestamp: u64, path: &str, trigger: Trigger) -> HistoryEntry {
+        HistoryEntry {
+            timestamp,
+            name: path.trim_start_matches('/').to_string(),
+            path: path.to_string(),
+            editor: "code".to_string(),
+            trigger,
+        }
+    }
+
+    fn history(dir: &Path, max_file_size: u64, max_files: usize) -> History {
+        History::with_path(
+            dir.join("history.jsonl"),
+            GitLauncherHistoryConfig {
+                max_file_size,
+                max_files,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn timestamps(history: &History) -> Vec<u64> {
+        history
+            .entries()
+            .iter()
+            .map(|entry| entry.timestamp)
+            .collect()
+    }
+
+    #[test]
+    fn rotates_and_drops_the_oldest_file() {
+        let dir = tempfile::tempdir().unwrap();
+        // every entry fills a file, so each append rotates
+        let history = history(dir.path(), 1, 2);
+        for timestamp in 1..=4 {
+            history
+                .append(&entry(timestamp, "/a", Trigger::Cli))
+                .unwrap();
+        }
+
+        assert_eq!(timestamps(&history), [2, 3, 4]);
+        assert!(dir.path().join("history.jsonl.2").exists());
+        assert!(!dir.path().join("history.jsonl.3").exists());
+    }
+
+    #[test]
+    fn without_rotated_files_only_the_live_file_is_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = history(dir.path(), 1, 0);
+        history.append(&entry(1, "/a", Trigger::Cli)).unwrap();
+        history.append(&entry(2, "/a", Trigger::Cli)).unwrap();
+
+        assert_eq!(timestamps(&history), [2]);
+    }
+
+    #[test]
+    fn recent_lists_each_repo_once_and_sees_appends() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = history(dir.path(), 1024 * 1024, 2);
+        for (timestamp, path) in [(1, "/a"), (2, "/b"), (3, "/a"), (4, "/c")] {
+            history
+                .append(&entry(timestamp, path, Trigger::Click))
+                .unwrap();
+        }
+
+        let paths = |recent: Vec<HistoryEntry>| -> Vec<String> {
+            recent.into_iter().map(|entry| entry.path).collect()
+        };
+        assert_eq!(paths(history.recent(10)), ["/c", "/a", "/b"]);
+        assert_eq!(paths(history.recent(2)), ["/c", "/a"]);
+
+        history.append(&entry(5, "/b", Trigger::Click)).unwrap();
+        assert_eq!(paths(history.recent(2)), ["/b", "/c"]);
+    }
+
+    #[test]
+    fn usage_stats_count_by_repo_editor_trigger_and_hour() {
+        let entries = [
+            entry(3600, "/b", Trigger::Cli),
+            entry(7200, "/a", Trigger::Keyboard),
+            entry(3600 * 25, "/b", Trigger::Keyboard),
+            entry(3600 * 2 + 5, "/c", Trigger::Keyboard),
+        ];
+        let stats = UsageStats::from_entries(&entries);
+
+        assert_eq!(stats.total, 4);
+        assert_eq!((stats.first, stats.last), (Some(3600), Some(3600 * 25)));
+        // ties are broken by name
+        assert_eq!(
+            stats.by_repo,
+            [
+                ("/b".to_string(), 2),
+                ("/a".to_string(), 1),
+                ("/c".to_string(), 1)
+            ]
+        );
+        assert_eq!(stats.by_editor, [("code".to_string(), 4)]);
+        assert_eq!(
+            stats.by_trigger,
+            [("keyboard".to_string(), 3), ("cli".to_string(), 1)]
+        );
+        assert_eq!((stats.by_hour[1], stats.by_hour[2]), (2, 2));
+        assert_eq!(stats.by_hour.iter().sum::<usize>(), 4);
+    }
+}
@@ -0,0 +1,91 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    sync::LazyLock,
+};
+
+use gpui::SharedString;
+use tokei::LanguageType;
+
+use crate::{
+    assets::{Assets, USER_ICON_PREFIX},
+    config::USER_ICONS_PATH,
+};
+
+use super::{language_by_name, normalize_language};
+
+/// the icon of every language tokei knows, resolved once at startup
+static REGISTRY: LazyLock<IconRegistry> = LazyLock::new(IconRegistry::load);
+
+struct IconRegistry {
+    icons: HashMap<LanguageType, SharedString>,
+    default: SharedString,
+}
+
+impl IconRegistry {
+    fn load() -> Self {
+        let user: HashSet<String> = fs::read_dir(&*USER_ICONS_PATH)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|entry| entry.file_name().to_string_lossy().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let default = resolve(&["default".to_string()], &user)
+            .unwrap_or_else(|| "languages/default.png".into());
+        let icons = LanguageType::list()
+            .iter()
+            .map(|language| {
+                let icon = resolve(&icon_keys(*language), &user).unwrap_or(default.clone());
+                (*language, icon)
+            })
+            .collect();
+
+        Self { icons, default }
+    }
+}
+
+/// the file names an icon of `language` may have, most specific first: the
+/// language itself, then the language it belongs to (`C Header` uses `c`)
+fn icon_keys(language: LanguageType) -> Vec<String> {
+    let key = normalize_language(language.name());
+    let family = match key.as_str() {
+        "cheader" => Some("c"),
+        "cppheader" => Some("cpp"),
+        "objectivec" | "objectivecpp" => Some("oc"),
+        "jsx" => Some("javascript"),
+        "tsx" => Some("typescript"),
+        "scss" => Some("sass"),
+        _ => None,
+    };
+    std::iter::once(key)
+        .chain(family.map(str::to_string))
+        .collect()
+}
+
+/// a user icon in `~/.git-launcher/icons/<key>.svg|png` wins over the
+/// bundled `languages/<key>.png`
+fn resolve(keys: &[String], user: &HashSet<String>) -> Option<SharedString> {
+    keys.iter().find_map(|key| {
+        ["svg", "png"]
+            .iter()
+            .map(|extension| format!("{key}.{extension}"))
+            .find(|file| user.contains(file))
+            .map(|file| format!("{USER_ICON_PREFIX}{file}"))
+            .or_else(|| {
+                let bundled = format!("languages/{key}.png");
+                Assets::get(&bundled).is_some().then_some(bundled)
+            })
+            .map(SharedString::from)
+    })
+}
+
+/// asset path of the icon for a language name as stored on `Repo`
+pub fn language_icon(language: &str) -> SharedString {
+    language_by_name(language)
+        .and_then(|language| REGISTRY.icons.get(&language))
+        .unwrap_or(&REGISTRY.default)
+        .clone()
+}
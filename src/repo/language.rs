@@ -1,74 +1,705 @@
-use std::{fs, path::PathBuf};
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use tokei::LanguageType;
+
+use crate::config::GitLauncherAnalysisConfig;
+
+use super::{
+    Analysis, CodeStats, FNV_OFFSET, FileStat, GitAttributes, Repo, fnv1a, has_generated_marker,
+    is_documentation, is_generated_name, is_vendored,
+};
+
+/// the walk stops after this many times `max_files`, the sample is drawn from
+/// what was found so far
+const WALK_FACTOR: usize = 4;
 
 pub struct LanguageAnalyzer {
     repo_path: String,
+    /// replaces git's global excludes file (`core.excludesFile`)
+    excludes_file: Option<PathBuf>,
+    max_files: Option<usize>,
+    max_bytes: Option<u64>,
+    timeout: Option<Duration>,
 }
 
 impl LanguageAnalyzer {
     pub fn new<S: Into<String>>(repo_path: S) -> Self {
         Self {
             repo_path: repo_path.into(),
+            excludes_file: None,
+            max_files: None,
+            max_bytes: None,
+            timeout: None,
         }
     }
 
-    fn resolve_git_ignore(&self) -> anyhow::Result<Vec<String>> {
-        let repo_path = PathBuf::from(&self.repo_path);
-        let git_ignore_path = repo_path.join(".gitignore");
-        if !git_ignore_path.exists() {
-            return Ok(Vec::new());
+    /// count a sample of the repo when it has more files or bytes than
+    /// allowed, and stop at the timeout
+    pub fn with_limits(mut self, config: &GitLauncherAnalysisConfig) -> Self {
+        self.max_files = Some(config.max_files).filter(|max| *max > 0);
+        self.max_bytes = Some(config.max_bytes).filter(|max| *max > 0);
+        self.timeout = Some(config.timeout_secs)
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs);
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_excludes_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.excludes_file = Some(path.into());
+        self
+    }
+
+    /// files git tracks or would offer to track: `.gitignore` files at every
+    /// level (including the ones above a workspace member), `.git/info/exclude`
+    /// and the global excludes file apply with git's rules, nested repos and
+    /// submodules are left to themselves
+    pub fn source_files(&self) -> impl Iterator<Item = PathBuf> {
+        let root = PathBuf::from(&self.repo_path);
+        let mut walker = WalkBuilder::new(&root);
+        walker
+            .hidden(false)
+            .ignore(false)
+            .git_ignore(true)
+            .git_exclude(true)
+            .git_global(self.excludes_file.is_none())
+            .parents(true)
+            .follow_links(false);
+        if let Some(excludes_file) = &self.excludes_file {
+            walker.add_ignore(excludes_file);
         }
-        let git_ignore_content = fs::read_to_string(git_ignore_path)?;
-        Ok(git_ignore_content
-            .lines()
-            .map(|line| line.to_string())
-            .collect())
+        walker.filter_entry(move |entry| {
+            if entry.file_name() == ".git" {
+                return false;
+            }
+            let is_dir = entry.file_type().is_some_and(|kind| kind.is_dir());
+            !(is_dir && entry.depth() > 0 && entry.path().join(".git").exists())
+        });
+
+        walker
+            .build()
+            .flatten()
+            .filter(|entry| entry.file_type().is_some_and(|kind| kind.is_file()))
+            .map(|entry| entry.into_path())
     }
 
-    pub fn language(&self) -> anyhow::Result<(String, u32)> {
-        let git_ignore = self.resolve_git_ignore()?;
+    /// languages, largest files and top-level directories of the repo, the
+    /// largest language by lines of code first
+    pub fn code_stats(&self) -> anyhow::Result<CodeStats> {
+        let (files, partial) = self.files()?;
+        Ok(CodeStats {
+            partial,
+            ..CodeStats::from_files(files)
+        })
+    }
 
+    /// lines of every source file that counts, files tokei can't parse are
+    /// left out. `true` when a limit was hit and only a sample was counted.
+    /// only the sampled files are opened, everything before that goes by path
+    fn files(&self) -> anyhow::Result<(Vec<FileStat>, bool)> {
         let config = tokei::Config::default();
-        let mut lang = tokei::Languages::new();
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        let timed_out = || deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        let max_walked = self.max_files.map(|max| max.saturating_mul(WALK_FACTOR));
+        let mut partial = false;
 
-        lang.get_statistics(
-            &[self.repo_path.as_str()],
-            &git_ignore.iter().map(String::as_str).collect::<Vec<&str>>(),
-            &config,
-        );
+        let root = PathBuf::from(&self.repo_path);
+        let mut files = Vec::new();
+        for path in self.source_files() {
+            if timed_out() || max_walked.is_some_and(|max| files.len() >= max) {
+                partial = true;
+                break;
+            }
+            files.push(path);
+        }
+        let nested: Vec<PathBuf> = files
+            .iter()
+            .filter(|path| {
+                path.file_name()
+                    .is_some_and(|name| name == ".gitattributes")
+            })
+            .cloned()
+            .collect();
+        let attributes = GitAttributes::load(&root, &nested);
+
+        let mut candidates = Vec::new();
+        for path in files {
+            let linguist = attributes.lookup(&path);
+            let relative = path
+                .strip_prefix(&root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            if linguist.vendored.unwrap_or_else(|| is_vendored(&relative))
+                || linguist
+                    .generated
+                    .unwrap_or_else(|| is_generated_name(&relative))
+                || linguist
+                    .documentation
+                    .unwrap_or_else(|| is_documentation(&relative))
+            {
+                continue;
+            }
+
+            let language_type = match &linguist.language {
+                Some(name) => language_by_name(name),
+                None => LanguageType::from_path(&path, &config),
+            };
+            let Some(language_type) = language_type else {
+                continue;
+            };
+            // `linguist-generated=false` overrides the marker
+            let sniff = linguist.generated.is_none();
+            candidates.push((path, relative, language_type, sniff));
+        }
+
+        // a fixed shuffle, so a sample or a timeout covers the whole tree
+        // instead of the first directories, and the same sample is taken again
+        candidates.sort_by_cached_key(|(_, relative, _, _)| fnv1a(FNV_OFFSET, relative.as_bytes()));
+
+        let mut stats = Vec::new();
+        let mut bytes = 0;
+        for (path, relative, language_type, sniff) in candidates {
+            if self.max_files.is_some_and(|max| stats.len() >= max) || timed_out() {
+                partial = true;
+                break;
+            }
+            let size = path.metadata().map(|meta| meta.len()).unwrap_or_default();
+            if self.max_bytes.is_some_and(|max| bytes + size > max) {
+                partial = true;
+                continue;
+            }
+            if sniff && has_generated_marker(&path) {
+                continue;
+            }
+
+            let Ok(report) = language_type.parse(path, &config) else {
+                continue;
+            };
+            bytes += size;
+            stats.push(FileStat {
+                path: relative,
+                language: language_type.to_string(),
+                code: report.stats.code as u32,
+                comments: report.stats.comments as u32,
+                blanks: report.stats.blanks as u32,
+            });
+        }
+
+        Ok((stats, partial))
+    }
+}
+
+/// tokei's language for a linguist name like `C++`, `cpp` or `Objective-C`
+pub fn language_by_name(name: &str) -> Option<LanguageType> {
+    let name = name.replace('-', " ");
+    name.parse().ok().or_else(|| {
+        let normalized = normalize_language(&name);
+        LanguageType::list()
+            .iter()
+            .copied()
+            .find(|language| normalize_language(language.name()) == normalized)
+    })
+}
+
+/// lines and files of one language in a repo
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LanguageStat {
+    pub name: String,
+    pub code: u32,
+    pub comments: u32,
+    pub blanks: u32,
+    pub files: u32,
+}
+
+/// languages with at least this share of the code match `lang:` filters
+pub const SIGNIFICANT_SHARE: f64 = 0.1;
 
-        let max_lang = lang
+/// compare language names loosely, `c++` matches `cpp` and `C#` matches `csharp`
+pub fn normalize_language(name: &str) -> String {
+    name.to_lowercase()
+        .replace('+', "p")
+        .replace('#', "sharp")
+        .replace([' ', '-', '_'], "")
+}
+
+impl Repo {
+    /// store a new breakdown, the top language becomes `language`
+    pub fn set_analysis(&mut self, analysis: Analysis) {
+        self.language = analysis
+            .languages
+            .first()
+            .map(|stat| stat.name.clone())
+            .unwrap_or_else(|| "unknown".to_string());
+        self.languages = analysis.languages;
+        self.languages_partial = analysis.partial;
+        self.activity = analysis.activity;
+    }
+
+    /// share of the code written in `language`, by lines of code
+    pub fn language_share(&self, language: &str) -> f64 {
+        let language = normalize_language(language);
+        let total: u64 = self.languages.iter().map(|stat| stat.code as u64).sum();
+        if total == 0 {
+            // repos analysed before breakdowns were kept only know their top language
+            return if normalize_language(&self.language) == language {
+                1.
+            } else {
+                0.
+            };
+        }
+        self.languages
             .iter()
-            .max_by_key(|(_, language)| language.code)
-            .map(|(lang_type, language)| (lang_type.to_string(), language.code))
-            .unwrap_or(("unknown".to_string(), 0));
+            .filter(|stat| normalize_language(&stat.name) == language)
+            .map(|stat| stat.code as f64 / total as f64)
+            .sum()
+    }
+
+    /// `(language, share)` for the language bar, small languages are merged
+    /// into one `Other` entry at the end
+    pub fn language_bar(&self, min_share: f64) -> Vec<(String, f64)> {
+        let total: u64 = self.languages.iter().map(|stat| stat.code as u64).sum();
+        if total == 0 {
+            return Vec::new();
+        }
 
-        Ok((max_lang.0.to_string(), max_lang.1 as u32))
+        let mut bar = Vec::new();
+        let mut other = 0.;
+        for stat in &self.languages {
+            let share = stat.code as f64 / total as f64;
+            if share >= min_share {
+                bar.push((stat.name.clone(), share));
+            } else {
+                other += share;
+            }
+        }
+        if other > 0. {
+            bar.push(("Other".to_string(), other));
+        }
+        bar
     }
 }
 
-pub fn get_language_icon(language: &str) -> String {
-    let language = language.to_lowercase();
-    match language.as_str() {
-        "javascript" => "javascript".to_string(),
-        "python" => "python".to_string(),
-        "rust" => "rust".to_string(),
-        "java" => "java".to_string(),
-        "c++" => "c++".to_string(),
-        "c#" => "c#".to_string(),
-        "c" => "c".to_string(),
-        "dart" => "dart".to_string(),
-        "go" => "go".to_string(),
-        "php" => "php".to_string(),
-        "zig" => "zig".to_string(),
-        "swift" => "swift".to_string(),
-        "typescript" => "typescript".to_string(),
-        "kotlin" => "kotlin".to_string(),
-        "json" => "json".to_string(),
-        "sass" => "sass".to_string(),
-        "markdown" => "markdown".to_string(),
-        "objective-c" => "oc".to_string(),
-        "css" => "css".to_string(),
-
-        _ => "default".to_string(),
+/// github linguist colors of common languages as `0xrrggbb`
+pub fn language_color(language: &str) -> u32 {
+    match normalize_language(language).as_str() {
+        "rust" => 0xdea584,
+        "javascript" | "jsx" => 0xf1e05a,
+        "typescript" | "tsx" => 0x3178c6,
+        "python" => 0x3572a5,
+        "java" => 0xb07219,
+        "kotlin" => 0xa97bff,
+        "go" => 0x00add8,
+        "c" | "cheader" => 0x555555,
+        "cpp" | "cppheader" => 0xf34b7d,
+        "csharp" => 0x178600,
+        "objectivec" => 0x438eff,
+        "swift" => 0xf05138,
+        "dart" => 0x00b4ab,
+        "php" => 0x4f5d95,
+        "ruby" => 0x701516,
+        "zig" => 0xec915c,
+        "html" => 0xe34c26,
+        "css" => 0x563d7c,
+        "sass" | "scss" => 0xc6538c,
+        "vue" => 0x41b883,
+        "svelte" => 0xff3e00,
+        "shell" | "bash" | "zsh" => 0x89e051,
+        "lua" => 0x000080,
+        "markdown" => 0x083fa1,
+        "json" => 0x292929,
+        "toml" => 0x9c4221,
+        "yaml" => 0xcb171e,
+        "nix" => 0x7e7eff,
+        "elixir" => 0x6e4a7e,
+        "haskell" => 0x5e5086,
+        "scala" => 0xc22d40,
+        _ => 0x8b949e,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path, process::Command};
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// a repo with an empty `.git` dir and the given files
+    fn fixture(files: &[(&str, &str)]) -> TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".git/info")).unwrap();
+        for (path, content) in files {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    /// an empty excludes file so the machine's global excludes don't leak in
+    fn analyzer(root: &Path) -> LanguageAnalyzer {
+        let excludes = root.join(".git/test-excludes");
+        if !excludes.exists() {
+            fs::write(&excludes, "").unwrap();
+        }
+        LanguageAnalyzer::new(root.to_string_lossy()).with_excludes_file(excludes)
+    }
+
+    fn relative_files(analyzer: &LanguageAnalyzer, root: &Path) -> Vec<String> {
+        let mut files: Vec<String> = analyzer
+            .source_files()
+            .map(|path| {
+                path.strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn comments_blank_lines_and_negations() {
+        let repo = fixture(&[
+            (".gitignore", "# main.rs\n\n*.log\n!keep.log\nbuild/\n"),
+            ("main.rs", "fn main() {}\n"),
+            ("debug.log", ""),
+            ("keep.log", ""),
+            ("build/out.rs", ""),
+            ("src/build.rs", ""),
+            ("src/build/gen.rs", ""),
+        ]);
+
+        assert_eq!(
+            relative_files(&analyzer(repo.path()), repo.path()),
+            [".gitignore", "keep.log", "main.rs", "src/build.rs"]
+        );
+    }
+
+    #[test]
+    fn anchored_patterns() {
+        let repo = fixture(&[
+            (".gitignore", "/dist\ndocs/*.md\n"),
+            ("dist/app.js", ""),
+            ("web/dist/app.js", ""),
+            ("docs/intro.md", ""),
+            ("docs/api/intro.md", ""),
+        ]);
+
+        assert_eq!(
+            relative_files(&analyzer(repo.path()), repo.path()),
+            [".gitignore", "docs/api/intro.md", "web/dist/app.js"]
+        );
+    }
+
+    #[test]
+    fn nested_gitignore_applies_below_its_dir() {
+        let repo = fixture(&[
+            ("gen.rs", ""),
+            ("sub/.gitignore", "gen.rs\n"),
+            ("sub/gen.rs", ""),
+            ("sub/lib.rs", ""),
+            ("sub/deeper/gen.rs", ""),
+        ]);
+
+        assert_eq!(
+            relative_files(&analyzer(repo.path()), repo.path()),
+            ["gen.rs", "sub/.gitignore", "sub/lib.rs"]
+        );
+    }
+
+    #[test]
+    fn nested_gitignore_can_unignore() {
+        let repo = fixture(&[
+            (".gitignore", "*.gen.rs\n"),
+            ("a.gen.rs", ""),
+            ("keep/.gitignore", "!*.gen.rs\n"),
+            ("keep/b.gen.rs", ""),
+        ]);
+
+        assert_eq!(
+            relative_files(&analyzer(repo.path()), repo.path()),
+            [".gitignore", "keep/.gitignore", "keep/b.gen.rs"]
+        );
+    }
+
+    #[test]
+    fn info_exclude() {
+        let repo = fixture(&[
+            (".git/info/exclude", "# local only\nsecret.py\n"),
+            ("secret.py", ""),
+            ("app.py", ""),
+        ]);
+
+        assert_eq!(
+            relative_files(&analyzer(repo.path()), repo.path()),
+            ["app.py"]
+        );
+    }
+
+    #[test]
+    fn global_excludes_file() {
+        let repo = fixture(&[("a.rs", ""), ("a.rs.swp", ""), (".DS_Store", "")]);
+        let excludes = repo.path().join(".git/global-excludes");
+        fs::write(&excludes, "*.swp\n.DS_Store\n").unwrap();
+
+        let analyzer =
+            LanguageAnalyzer::new(repo.path().to_string_lossy()).with_excludes_file(excludes);
+        assert_eq!(relative_files(&analyzer, repo.path()), ["a.rs"]);
+    }
+
+    #[test]
+    fn member_sees_ignore_files_of_its_repo() {
+        let repo = fixture(&[
+            (".gitignore", "generated/\n"),
+            ("crates/core/src/lib.rs", ""),
+            ("crates/core/generated/schema.rs", ""),
+        ]);
+        let member = repo.path().join("crates/core");
+        let excludes = repo.path().join(".git/test-excludes");
+        fs::write(&excludes, "").unwrap();
+
+        let analyzer = LanguageAnalyzer::new(member.to_string_lossy()).with_excludes_file(excludes);
+        assert_eq!(relative_files(&analyzer, &member), ["src/lib.rs"]);
+    }
+
+    #[test]
+    fn nested_repos_are_skipped() {
+        let repo = fixture(&[
+            ("src/main.rs", ""),
+            ("vendor/lib/.git/HEAD", ""),
+            ("vendor/lib/src/lib.rs", ""),
+        ]);
+
+        assert_eq!(
+            relative_files(&analyzer(repo.path()), repo.path()),
+            ["src/main.rs"]
+        );
+    }
+
+    #[test]
+    fn languages_only_count_source_files() {
+        let repo = fixture(&[
+            (".gitignore", "target/\nnode_modules/\n"),
+            (
+                "src/main.rs",
+                "// entry\nfn main() {\n\n    println!(\"hi\");\n}\n",
+            ),
+            ("target/debug/build.rs", "fn a() {}\nfn b() {}\nfn c() {}\n"),
+            ("node_modules/dep/index.js", "module.exports = 1;\n"),
+        ]);
+
+        let stats = analyzer(repo.path()).code_stats().unwrap().languages;
+        assert_eq!(
+            stats,
+            [LanguageStat {
+                name: "Rust".to_string(),
+                code: 3,
+                comments: 1,
+                blanks: 1,
+                files: 1,
+            }]
+        );
+    }
+
+    fn language_names(stats: &[LanguageStat]) -> Vec<(&str, u32)> {
+        stats
+            .iter()
+            .map(|stat| (stat.name.as_str(), stat.files))
+            .collect()
+    }
+
+    #[test]
+    fn gitattributes_exclude_vendored_generated_and_docs() {
+        let repo = fixture(&[
+            (
+                ".gitattributes",
+                concat!(
+                    "static/** linguist-vendored\n",
+                    "*.gen.ts linguist-generated=true\n",
+                    "guide/** linguist-documentation\n",
+                ),
+            ),
+            ("src/main.rs", "fn main() {}\n"),
+            ("static/lib.js", "var a = 1;\n"),
+            ("api/client.gen.ts", "let a = 1;\n"),
+            ("guide/setup.py", "a = 1\n"),
+        ]);
+
+        let stats = analyzer(repo.path()).code_stats().unwrap().languages;
+        assert_eq!(language_names(&stats), [("Rust", 1)]);
+    }
+
+    #[test]
+    fn gitattributes_override_heuristics_and_languages() {
+        let repo = fixture(&[
+            (
+                ".gitattributes",
+                "vendor/ours/** -linguist-vendored\n*.inc linguist-language=C++\n",
+            ),
+            ("vendor/ours/lib.go", "package ours\n"),
+            ("vendor/theirs/lib.go", "package theirs\n"),
+            ("src/table.inc", "int a = 1;\n"),
+            ("sub/.gitattributes", "*.inc -linguist-language\n"),
+            ("sub/table.inc", "int b = 2;\n"),
+        ]);
+
+        let stats = analyzer(repo.path()).code_stats().unwrap().languages;
+        assert_eq!(language_names(&stats), [("C++", 1), ("Go", 1)]);
+    }
+
+    #[test]
+    fn vendored_and_generated_heuristics() {
+        let repo = fixture(&[
+            ("src/app.js", "let a = 1;\n"),
+            ("public/jquery.min.js", "var a = 1;\n"),
+            ("third_party/zlib/inflate.c", "int a;\n"),
+            ("api/service.pb.go", "package api\n"),
+            (
+                "api/models.go",
+                "// Code generated by sqlc. DO NOT EDIT.\npackage api\n",
+            ),
+            ("docs/conf.py", "a = 1\n"),
+        ]);
+
+        let stats = analyzer(repo.path()).code_stats().unwrap().languages;
+        assert_eq!(language_names(&stats), [("JavaScript", 1)]);
+    }
+
+    #[test]
+    fn code_stats_by_file_and_directory() {
+        let repo = fixture(&[
+            ("build.rs", "fn main() {}\n"),
+            ("src/lib.rs", "fn a() {}\nfn b() {}\n"),
+            ("src/main.rs", "fn main() {}\n"),
+            ("web/app.js", "let a = 1;\nlet b = 2;\nlet c = 3;\n"),
+        ]);
+
+        let stats = analyzer(repo.path()).code_stats().unwrap();
+        assert_eq!(
+            language_names(&stats.languages),
+            [("Rust", 3), ("JavaScript", 1)]
+        );
+        assert_eq!(stats.total().code, 7);
+
+        let largest: Vec<&str> = stats
+            .largest_files
+            .iter()
+            .map(|file| file.path.as_str())
+            .collect();
+        assert_eq!(
+            largest,
+            ["web/app.js", "src/lib.rs", "build.rs", "src/main.rs"]
+        );
+
+        let directories: Vec<(&str, Vec<(&str, u32)>)> = stats
+            .directories
+            .iter()
+            .map(|dir| (dir.name.as_str(), language_names(&dir.languages)))
+            .collect();
+        assert_eq!(
+            directories,
+            [
+                (".", vec![("Rust", 1)]),
+                ("src", vec![("Rust", 2)]),
+                ("web", vec![("JavaScript", 1)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn limits_fall_back_to_a_sample() {
+        let repo = fixture(&[
+            ("a.rs", "fn a() {}\n"),
+            ("b.rs", "fn b() {}\n"),
+            ("c.rs", "fn c() {}\n"),
+            ("d.rs", "fn d() {}\n"),
+        ]);
+        let limits = |max_files, max_bytes| GitLauncherAnalysisConfig {
+            max_files,
+            max_bytes,
+            timeout_secs: 0,
+            ..Default::default()
+        };
+
+        let full = analyzer(repo.path())
+            .with_limits(&limits(4, 0))
+            .code_stats()
+            .unwrap();
+        assert!(!full.partial);
+        assert_eq!(full.total().files, 4);
+
+        let by_files = analyzer(repo.path())
+            .with_limits(&limits(2, 0))
+            .code_stats()
+            .unwrap();
+        assert!(by_files.partial);
+        assert_eq!(by_files.total().files, 2);
+
+        let by_bytes = analyzer(repo.path())
+            .with_limits(&limits(0, 25))
+            .code_stats()
+            .unwrap();
+        assert!(by_bytes.partial);
+        assert_eq!(by_bytes.total().files, 2);
+    }
+
+    /// the walk agrees with `git ls-files` on a real repository
+    #[test]
+    fn matches_git() {
+        let repo = fixture(&[
+            (
+                ".gitignore",
+                "*.log\n!keep.log\n/out\nbuild/\n# c\n\\#hash.rs\n",
+            ),
+            ("keep.log", ""),
+            ("debug.log", ""),
+            ("out/a.rs", ""),
+            ("src/out/a.rs", ""),
+            ("src/build/b.rs", ""),
+            ("#hash.rs", ""),
+            ("lib/.gitignore", "*.tmp\n!important.tmp\n"),
+            ("lib/x.tmp", ""),
+            ("lib/important.tmp", ""),
+            ("lib/mod.rs", ""),
+        ]);
+        fs::remove_dir_all(repo.path().join(".git")).unwrap();
+        let init = Command::new("git")
+            .args(["init", "-q"])
+            .current_dir(repo.path())
+            .status();
+        if !init.is_ok_and(|status| status.success()) {
+            eprintln!("git not available, skipping");
+            return;
+        }
+        let excludes = repo.path().join(".git/test-excludes");
+        fs::write(&excludes, "").unwrap();
+
+        let output = Command::new("git")
+            .args(["-c"])
+            .arg(format!("core.excludesFile={}", excludes.display()))
+            .args(["ls-files", "--cached", "--others", "--exclude-standard"])
+            .current_dir(repo.path())
+            .output()
+            .unwrap();
+        let mut expected: Vec<String> = String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect();
+        expected.sort();
+
+        assert_eq!(
+            relative_files(&analyzer(repo.path()), repo.path()),
+            expected
+        );
     }
 }
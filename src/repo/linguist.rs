@@ -0,0 +1,301 @@
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use globset::{GlobBuilder, GlobMatcher};
+
+use super::git_dir;
+
+/// linguist attributes of one file, `None` where no `.gitattributes` rule
+/// says anything and the built-in heuristics decide
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinguistAttributes {
+    pub vendored: Option<bool>,
+    pub generated: Option<bool>,
+    pub documentation: Option<bool>,
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+    Set,
+    Unset,
+    Text(String),
+    /// `!attr`, back to unspecified
+    Unspecified,
+}
+
+#[derive(Debug)]
+struct Rule {
+    matcher: GlobMatcher,
+    attributes: Vec<(String, Value)>,
+}
+
+/// the `linguist-*` rules of every `.gitattributes` that applies to a repo,
+/// in git's precedence order: parent directories before children and
+/// `.git/info/attributes` last
+#[derive(Debug, Default)]
+pub struct GitAttributes {
+    /// the git top level, rule patterns are relative to it
+    top: PathBuf,
+    rules: Vec<Rule>,
+}
+
+impl GitAttributes {
+    /// read the attribute files above `path` up to the git top level, the
+    /// ones in `nested` (`.gitattributes` files found while walking `path`)
+    /// and `.git/info/attributes`
+    pub fn load(path: &Path, nested: &[PathBuf]) -> Self {
+        let top = path
+            .ancestors()
+            .find(|dir| dir.join(".git").exists())
+            .unwrap_or(path)
+            .to_path_buf();
+        let mut attributes = GitAttributes {
+            top: top.clone(),
+            rules: Vec::new(),
+        };
+
+        let mut files: Vec<PathBuf> = path
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(&top))
+            .map(|dir| dir.join(".gitattributes"))
+            .collect();
+        files.reverse();
+        let mut nested = nested.to_vec();
+        // shallow files first, deeper ones override them
+        nested.sort_by_key(|file| file.components().count());
+        files.extend(nested);
+
+        for file in files {
+            let base = file.parent().unwrap_or(&top).to_path_buf();
+            attributes.read(&file, &base);
+        }
+        if let Some(git_dir) = git_dir(&top) {
+            attributes.read(&git_dir.join("info/attributes"), &top);
+        }
+        attributes
+    }
+
+    fn read(&mut self, file: &Path, base: &Path) {
+        let Ok(content) = fs::read_to_string(file) else {
+            return;
+        };
+        let Ok(base) = base.strip_prefix(&self.top) else {
+            return;
+        };
+        let base = base.to_string_lossy().replace('\\', "/");
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let Some(pattern) = parts.next() else {
+                continue;
+            };
+            let attributes: Vec<(String, Value)> = parts
+                .filter_map(parse_attribute)
+                .filter(|(name, _)| name.starts_with("linguist-"))
+                .collect();
+            if attributes.is_empty() {
+                continue;
+            }
+            let Some(matcher) = glob(&base, pattern) else {
+                continue;
+            };
+            self.rules.push(Rule {
+                matcher,
+                attributes,
+            });
+        }
+    }
+
+    /// attributes of `file`, later rules win like in git
+    pub fn lookup(&self, file: &Path) -> LinguistAttributes {
+        let mut result = LinguistAttributes::default();
+        let Ok(relative) = file.strip_prefix(&self.top) else {
+            return result;
+        };
+
+        for rule in self
+            .rules
+            .iter()
+            .filter(|rule| rule.matcher.is_match(relative))
+        {
+            for (name, value) in &rule.attributes {
+                let flag = match value {
+                    Value::Set => Some(true),
+                    Value::Unset => Some(false),
+                    Value::Text(text) => Some(text != "false"),
+                    Value::Unspecified => None,
+                };
+                match name.as_str() {
+                    "linguist-vendored" => result.vendored = flag,
+                    "linguist-generated" => result.generated = flag,
+                    "linguist-documentation" => result.documentation = flag,
+                    "linguist-language" => {
+                        result.language = match value {
+                            Value::Text(language) => Some(language.clone()),
+                            _ => None,
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        result
+    }
+}
+
+fn parse_attribute(attribute: &str) -> Option<(String, Value)> {
+    if let Some(name) = attribute.strip_prefix('-') {
+        return Some((name.to_string(), Value::Unset));
+    }
+    if let Some(name) = attribute.strip_prefix('!') {
+        return Some((name.to_string(), Value::Unspecified));
+    }
+    match attribute.split_once('=') {
+        Some((name, value)) => Some((name.to_string(), Value::Text(value.to_string()))),
+        None => Some((attribute.to_string(), Value::Set)),
+    }
+}
+
+/// a gitattributes pattern of a file in `base` as a glob from the top level:
+/// patterns with a slash are anchored to `base`, others match the file name
+/// at any depth below it
+fn glob(base: &str, pattern: &str) -> Option<GlobMatcher> {
+    let prefix = if base.is_empty() {
+        String::new()
+    } else {
+        format!("{base}/")
+    };
+    let glob = if pattern.trim_end_matches('/').contains('/') {
+        format!("{prefix}{}", pattern.trim_start_matches('/'))
+    } else {
+        format!("{prefix}**/{pattern}")
+    };
+    GlobBuilder::new(&glob)
+        .literal_separator(true)
+        .build()
+        .ok()
+        .map(|glob| glob.compile_matcher())
+}
+
+/// directories of third party code, a subset of linguist's `vendor.yml`
+const VENDORED_DIRS: [&str; 15] = [
+    "vendor",
+    "vendors",
+    "node_modules",
+    "bower_components",
+    "third_party",
+    "third-party",
+    "thirdparty",
+    "external",
+    "extern",
+    "deps",
+    "Pods",
+    "Carthage",
+    ".yarn",
+    "jspm_packages",
+    "Godeps",
+];
+
+/// file names of bundled libraries
+const VENDORED_FILES: [&str; 6] = [
+    "jquery",
+    "bootstrap",
+    "modernizr",
+    "underscore",
+    "d3",
+    "gradlew",
+];
+
+pub fn is_vendored(relative: &str) -> bool {
+    let mut segments = relative.split('/');
+    let file = segments.next_back().unwrap_or_default();
+    if segments.any(|dir| VENDORED_DIRS.contains(&dir)) {
+        return true;
+    }
+    let lower = file.to_lowercase();
+    lower.ends_with(".min.js")
+        || lower.ends_with(".min.css")
+        || VENDORED_FILES
+            .iter()
+            .any(|name| lower == *name || lower.starts_with(&format!("{name}.")))
+        || lower.starts_with("jquery-")
+}
+
+/// generated by name, like protoc output and lock files
+pub fn is_generated_name(relative: &str) -> bool {
+    let lower = relative
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    const SUFFIXES: [&str; 14] = [
+        ".pb.go",
+        ".pb.cc",
+        ".pb.h",
+        "_pb2.py",
+        "_pb2_grpc.py",
+        ".pb.swift",
+        ".g.dart",
+        ".freezed.dart",
+        ".designer.cs",
+        ".generated.cs",
+        "_generated.rs",
+        ".gen.go",
+        ".pb.ts",
+        "_pb.js",
+    ];
+    const FILES: [&str; 7] = [
+        "package-lock.json",
+        "yarn.lock",
+        "pnpm-lock.yaml",
+        "cargo.lock",
+        "go.sum",
+        "poetry.lock",
+        "composer.lock",
+    ];
+    SUFFIXES.iter().any(|suffix| lower.ends_with(suffix)) || FILES.contains(&lower.as_str())
+}
+
+/// a `DO NOT EDIT` / `@generated` marker in the first lines, like go, protoc
+/// and many other generators write
+pub fn has_generated_marker(path: &Path) -> bool {
+    let mut head = [0; 1024];
+    let Ok(read) = File::open(path).and_then(|mut file| file.read(&mut head)) else {
+        return false;
+    };
+    let head = String::from_utf8_lossy(&head[..read]);
+    head.lines()
+        .take(5)
+        .any(|line| line.contains("DO NOT EDIT") || line.contains("@generated"))
+}
+
+pub fn is_documentation(relative: &str) -> bool {
+    let mut segments = relative.split('/');
+    let file = segments.next_back().unwrap_or_default().to_lowercase();
+    let in_docs = segments.any(|dir| {
+        matches!(
+            dir.to_lowercase().as_str(),
+            "doc" | "docs" | "documentation" | "examples" | "example"
+        )
+    });
+    in_docs
+        || [
+            "readme",
+            "changelog",
+            "changes",
+            "license",
+            "licence",
+            "contributing",
+        ]
+        .iter()
+        .any(|name| file == *name || file.starts_with(&format!("{name}.")))
+}
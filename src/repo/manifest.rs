@@ -0,0 +1,41 @@
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+/// name of the per-repo override file read by the scanner
+pub const MANIFEST_FILE: &str = ".git-launcher.toml";
+
+/// launcher settings committed with a repository in `.git-launcher.toml`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RepoManifest {
+    pub display_name: Option<String>,
+    pub tags: Vec<String>,
+    /// editor application used instead of `editor_config.editor`
+    pub editor: Option<String>,
+    /// file opened instead of the repo folder, relative to the repo root
+    pub workspace: Option<String>,
+    pub actions: Vec<RepoAction>,
+}
+
+/// a shell command that can be run from the launcher inside the repo
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RepoAction {
+    pub name: String,
+    pub command: String,
+}
+
+impl RepoManifest {
+    /// read the manifest of the repo at `repo_path`, if it has one
+    pub fn load(repo_path: &Path) -> Option<Self> {
+        let path = repo_path.join(MANIFEST_FILE);
+        let content = fs::read_to_string(&path).ok()?;
+        match toml::from_str(&content) {
+            Ok(manifest) => Some(manifest),
+            Err(err) => {
+                eprintln!("ignoring invalid {}: {}", path.display(), err);
+                None
+            }
+        }
+    }
+}
@@ -1,15 +1,66 @@
+mod activity;
+mod analysis;
+mod analysis_cache;
+mod catalog;
+mod code_stats;
+mod disk_usage;
+mod facet;
+mod frecency;
+mod fuzzy;
+mod history;
+mod icon;
 mod language;
+mod linguist;
+mod manifest;
+mod priority;
+mod project_type;
+mod query;
+mod remote;
 mod search_repo;
+#[cfg(feature = "sqlite")]
+mod sqlite_store;
+mod storage;
+mod store;
+mod trust;
+mod volume;
+mod workspace;
 
 use std::{
     collections::HashSet,
     hash::{Hash, Hasher},
+    path::{Path, PathBuf},
     sync::{Arc, RwLock},
 };
 
 use gpui::{App, Global};
+
+use crate::config::{Config, GitLauncherConfig, GitLauncherEditorConfig, MemberTarget};
+pub use activity::*;
+pub use analysis::*;
+pub use analysis_cache::*;
+pub use catalog::*;
+pub use code_stats::*;
+pub use disk_usage::*;
+pub use facet::*;
+pub use frecency::*;
+pub use fuzzy::*;
+pub use history::*;
+pub use icon::*;
 pub use language::*;
+pub use linguist::*;
+pub use manifest::*;
+pub use priority::*;
+pub use project_type::*;
+pub use query::*;
+pub use remote::*;
 pub use search_repo::*;
+#[cfg(feature = "sqlite")]
+pub use sqlite_store::*;
+pub use storage::*;
+pub use store::*;
+pub use trust::*;
+pub use volume::*;
+pub use workspace::*;
 
 use serde::{Deserialize, Serialize};
 
@@ -18,7 +69,217 @@ pub struct Repo {
     pub name: String,
     pub path: String,
     pub language: String,
+    /// every language with its lines and files, largest first
+    #[serde(default)]
+    pub languages: Vec<LanguageStat>,
+    /// unix seconds of the last language analysis
+    #[serde(default)]
+    pub analyzed_at: Option<u64>,
+    /// `languages` were counted from a sample of a repo too large to count
+    #[serde(default)]
+    pub languages_partial: bool,
+    /// commit history, refreshed with the languages
+    #[serde(default)]
+    pub activity: Option<Activity>,
     pub count: u32,
+    #[serde(default)]
+    pub opens: Vec<u64>,
+    /// user chosen name shown instead of the folder name
+    #[serde(default)]
+    pub display_name: Option<String>,
+    /// extra names the repo can be found by
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// hidden from default results, still on disk
+    #[serde(default)]
+    pub archived: bool,
+    /// overrides from the repo's `.git-launcher.toml`, refreshed on every scan
+    #[serde(default)]
+    pub manifest: Option<RepoManifest>,
+    /// detected from build manifests on every scan
+    #[serde(default)]
+    pub project_types: Vec<ProjectType>,
+    /// url of the `origin` remote, read on every scan
+    #[serde(default)]
+    pub remote: Option<String>,
+    /// path of the workspace root for monorepo members
+    #[serde(default)]
+    pub parent: Option<String>,
+    /// measured in the background, kept across scans
+    #[serde(default)]
+    pub disk_usage: Option<DiskUsage>,
+    #[serde(default)]
+    pub volume: Option<RepoVolume>,
+    /// on a drive that is unplugged or unmounted, kept until it returns
+    #[serde(default)]
+    pub unavailable: bool,
+    /// added by `import` from outside the base dirs, scans keep it as long as
+    /// it is still a repo
+    #[serde(default)]
+    pub imported: bool,
+}
+
+impl From<GitProject> for Repo {
+    fn from(project: GitProject) -> Self {
+        Self {
+            name: project.folder_name,
+            path: project.full_path.to_string_lossy().to_string(),
+            language: String::from("unknown"),
+            languages: Vec::new(),
+            analyzed_at: None,
+            languages_partial: false,
+            activity: None,
+            count: 0,
+            opens: Vec::new(),
+            display_name: None,
+            aliases: Vec::new(),
+            archived: false,
+            manifest: None,
+            project_types: Vec::new(),
+            remote: None,
+            parent: None,
+            disk_usage: None,
+            volume: None,
+            unavailable: false,
+            imported: false,
+        }
+    }
+}
+
+/// a freshly scanned repo at `path`, shared by the tests of the storage backends
+#[cfg(test)]
+pub(crate) fn test_repo(path: &str) -> Repo {
+    Repo::from(GitProject {
+        full_path: PathBuf::from(path),
+        folder_name: path.trim_start_matches('/').to_string(),
+    })
+}
+
+impl Repo {
+    /// the name shown in results, a user set name wins over the manifest
+    pub fn title(&self) -> &str {
+        self.display_name
+            .as_deref()
+            .or_else(|| self.manifest.as_ref()?.display_name.as_deref())
+            .unwrap_or(&self.name)
+    }
+
+    /// whether the shown name differs from the folder name
+    pub fn is_renamed(&self) -> bool {
+        self.title() != self.name
+    }
+
+    /// every name the repo can be searched by
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.name.as_str())
+            .chain(self.display_name.as_deref())
+            .chain(
+                self.manifest
+                    .as_ref()
+                    .and_then(|manifest| manifest.display_name.as_deref()),
+            )
+            .chain(self.aliases.iter().map(String::as_str))
+    }
+
+    pub fn tags(&self) -> &[String] {
+        self.manifest
+            .as_ref()
+            .map(|manifest| manifest.tags.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn actions(&self) -> &[RepoAction] {
+        self.manifest
+            .as_ref()
+            .map(|manifest| manifest.actions.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// the editor to open the repo with, the manifest's choice only when the
+    /// config allows it
+    pub fn editor<'a>(&'a self, config: &'a GitLauncherEditorConfig) -> &'a str {
+        let Some(editor) = self
+            .manifest
+            .as_ref()
+            .and_then(|manifest| manifest.editor.as_deref())
+        else {
+            return &config.editor;
+        };
+        if editor == config.editor
+            || config
+                .allowed_editors
+                .iter()
+                .any(|allowed| allowed == editor)
+        {
+            return editor;
+        }
+        eprintln!(
+            "ignoring editor {} of {}, it is not in editor_config.allowed_editors",
+            editor, self.path
+        );
+        &config.editor
+    }
+
+    /// what to hand to the editor: the manifest's workspace file or the repo
+    /// folder, for workspace members optionally the root with the member's
+    /// manifest to focus it
+    pub fn open_paths(&self, member_target: MemberTarget) -> Vec<String> {
+        if let (Some(parent), MemberTarget::Root) = (&self.parent, member_target) {
+            let member = Path::new(&self.path);
+            let focus = ["Cargo.toml", "package.json", "go.mod", "project.json"]
+                .iter()
+                .map(|name| member.join(name))
+                .find(|manifest| manifest.exists())
+                .map(|manifest| manifest.to_string_lossy().to_string())
+                .unwrap_or_else(|| self.path.clone());
+            return vec![parent.clone(), focus];
+        }
+
+        let target = self
+            .manifest
+            .as_ref()
+            .and_then(|manifest| manifest.workspace.as_deref())
+            .and_then(|workspace| self.workspace_file(workspace))
+            .map(|workspace| workspace.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.path.clone());
+        vec![target]
+    }
+
+    /// the manifest's workspace file, if it exists inside the repo. `..`,
+    /// absolute paths and symlinks leading out of the repo are refused
+    fn workspace_file(&self, workspace: &str) -> Option<PathBuf> {
+        let root = Path::new(&self.path).canonicalize().ok()?;
+        let file = root.join(workspace).canonicalize().ok()?;
+        if file.starts_with(&root) {
+            return Some(file);
+        }
+        eprintln!(
+            "ignoring workspace {} of {}, it is outside the repo",
+            workspace, self.path
+        );
+        None
+    }
+
+    /// folder name of the workspace root for monorepo members
+    pub fn parent_name(&self) -> Option<String> {
+        let parent = Path::new(self.parent.as_ref()?);
+        Some(parent.file_name()?.to_string_lossy().to_string())
+    }
+
+    /// take over what a rescan knows about the repo, keeping usage data and
+    /// analysis results
+    pub fn update_from_scan(&mut self, scanned: Repo) {
+        self.name = scanned.name;
+        self.manifest = scanned.manifest;
+        self.project_types = scanned.project_types;
+        self.remote = scanned.remote;
+        self.parent = scanned.parent;
+        self.volume = scanned.volume;
+        self.unavailable = false;
+        if self.language == "unknown" {
+            self.language = scanned.language;
+        }
+    }
 }
 
 impl Hash for Repo {
@@ -42,10 +303,317 @@ pub struct RepoState {
 
 impl Global for RepoState {}
 
+impl RepoState {
+    /// load the state from the configured storage
+    pub fn load() -> anyhow::Result<Self> {
+        let repos = storage().load()?.repos;
+        Ok(Self {
+            repos: Arc::new(RwLock::new(repos.into_iter().collect())),
+        })
+    }
+
+    /// find a repo by path, then by any of its names
+    pub fn find(&self, ident: &str) -> Option<Repo> {
+        let repos = self.repos.read().unwrap();
+        let path = Path::new(ident)
+            .canonicalize()
+            .map(|path| path.to_string_lossy().to_string())
+            .unwrap_or_else(|_| ident.to_string());
+
+        repos
+            .iter()
+            .find(|repo| repo.path == path)
+            .or_else(|| {
+                repos
+                    .iter()
+                    .find(|repo| repo.names().any(|name| name == ident))
+            })
+            .cloned()
+    }
+
+    /// merge scan results into the stored state field by field and persist
+    /// it. repos on a volume that moved are relocated first, repos on offline
+    /// volumes are kept as unavailable, imported repos outside the base dirs
+    /// are kept while they exist and the rest that were not found again are
+    /// dropped
+    pub fn merge_scan(&self, scanned: Vec<Repo>, volumes: &[Volume]) -> anyhow::Result<()> {
+        self.transact(|repos| *repos = merge_scan(std::mem::take(repos), scanned, volumes))
+    }
+
+    /// replace the whole state with repos loaded from storage
+    pub fn reload(&self, repos: Vec<Repo>) {
+        *self.repos.write().unwrap() = repos.into_iter().collect();
+    }
+
+    /// apply `f` to the repos as currently stored and persist them as one
+    /// step, so changes another process saved since we loaded are kept. the
+    /// state is replaced with the result
+    pub fn transact<F: FnOnce(&mut HashSet<Repo>)>(&self, f: F) -> anyhow::Result<()> {
+        let mut repos = self.repos.write().unwrap();
+        let mut f = Some(f);
+        let stored = storage().transact(&mut |stored| {
+            let mut set: HashSet<Repo> = stored.drain(..).collect();
+            if let Some(f) = f.take() {
+                f(&mut set);
+            }
+            stored.extend(set);
+        })?;
+        *repos = stored.into_iter().collect();
+        Ok(())
+    }
+
+    /// apply `f` to the stored repo at `path` and persist it
+    pub fn update<F: FnOnce(&mut Repo)>(&self, path: &str, f: F) -> anyhow::Result<()> {
+        self.transact(|repos| {
+            let Some(mut repo) = repos.iter().find(|repo| repo.path == path).cloned() else {
+                return;
+            };
+            f(&mut repo);
+            repos.replace(repo);
+        })
+    }
+
+    /// apply `f` to every stored repo and persist them at once
+    pub fn update_all<F: FnMut(&mut Repo)>(&self, mut f: F) -> anyhow::Result<()> {
+        self.transact(|repos| {
+            *repos = repos
+                .drain()
+                .map(|mut repo| {
+                    f(&mut repo);
+                    repo
+                })
+                .collect();
+        })
+    }
+}
+
+/// the stored repos after a scan found `scanned` on `volumes`, see
+/// [`RepoState::merge_scan`]
+fn merge_scan(stored: HashSet<Repo>, scanned: Vec<Repo>, volumes: &[Volume]) -> HashSet<Repo> {
+    let base_dirs: Vec<String> = volumes.iter().map(|v| v.base_dir.clone()).collect();
+
+    let mut existing: HashSet<Repo> = stored
+        .into_iter()
+        .map(|mut repo| {
+            repo.volume = repo.volume_in(&base_dirs);
+            let mount = repo.volume.as_ref().and_then(|repo_volume| {
+                volumes
+                    .iter()
+                    .find(|volume| volume.base_dir == repo_volume.base_dir)
+                    .and_then(|volume| volume.mount.clone())
+                    .filter(|mount| *mount != repo_volume.mount)
+            });
+            if let Some(mount) = mount {
+                repo.relocate(&mount);
+            }
+            repo
+        })
+        .collect();
+
+    let mut merged = HashSet::with_capacity(scanned.len());
+    for repo in scanned {
+        let repo = match existing.take(&repo) {
+            Some(mut existing) => {
+                existing.update_from_scan(repo);
+                existing
+            }
+            None => repo,
+        };
+        merged.insert(repo);
+    }
+
+    for mut repo in existing {
+        let offline = repo.volume.as_ref().is_some_and(|repo_volume| {
+            volumes
+                .iter()
+                .any(|volume| volume.base_dir == repo_volume.base_dir && !volume.is_online())
+        });
+        if offline {
+            repo.unavailable = true;
+            merged.insert(repo);
+        } else if repo.imported && git_dir(Path::new(&repo.path)).is_some() {
+            merged.insert(repo);
+        }
+    }
+
+    merged
+}
+
+/// scan every mounted base dir for git repos
+pub async fn scan(config: &GitLauncherConfig, volumes: &[Volume]) -> anyhow::Result<Vec<Repo>> {
+    let repo_finder = GitProjectFinder::builder(config.clone()).build();
+
+    let mut repos = Vec::new();
+    for volume in volumes {
+        let Some(mount) = &volume.mount else {
+            continue;
+        };
+        let repo_volume = RepoVolume {
+            base_dir: volume.base_dir.clone(),
+            mount: mount.clone(),
+        };
+        let projects = repo_finder.find_git_projects(Path::new(mount)).await?;
+        for project in projects {
+            let mut repo = Repo::from(project);
+            repo.volume = Some(repo_volume.clone());
+            let root = PathBuf::from(&repo.path);
+            repo.manifest = RepoManifest::load(&root);
+            repo.project_types = ProjectType::detect(&root);
+            repo.remote = remote_url(&root);
+
+            if config.discover_members {
+                for member in discover_members(&root) {
+                    repos.push(Repo {
+                        name: member.name,
+                        path: member.path.to_string_lossy().to_string(),
+                        manifest: RepoManifest::load(&member.path),
+                        project_types: ProjectType::detect(&member.path),
+                        remote: repo.remote.clone(),
+                        parent: Some(repo.path.clone()),
+                        volume: Some(repo_volume.clone()),
+                        ..Repo::from(GitProject {
+                            full_path: member.path.clone(),
+                            folder_name: String::new(),
+                        })
+                    });
+                }
+            }
+
+            repos.push(repo);
+        }
+    }
+
+    Ok(repos)
+}
+
 /// init repo state
 pub fn init(cx: &mut App) -> Result<(), anyhow::Error> {
+    init_storage(&cx.global::<Config>().storage_config)?;
+
     cx.set_global(RepoState {
         repos: Arc::new(RwLock::new(HashSet::new())),
     });
+    cx.set_global(AnalysisQueue::default());
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "/Volumes/PSSD";
+
+    fn volume(mount: Option<&str>) -> Vec<Volume> {
+        vec![Volume {
+            base_dir: BASE.to_string(),
+            mount: mount.map(str::to_string),
+        }]
+    }
+
+    /// a repo the user renamed, opened and archived
+    fn customized(path: &str) -> Repo {
+        let mut repo = test_repo(path);
+        repo.display_name = Some("App".to_string());
+        repo.count = 3;
+        repo.archived = true;
+        repo
+    }
+
+    fn imported(path: &str) -> Repo {
+        let mut repo = test_repo(path);
+        repo.imported = true;
+        repo
+    }
+
+    struct Case<'a> {
+        name: &'a str,
+        stored: Vec<Repo>,
+        scanned: Vec<Repo>,
+        volumes: Vec<Volume>,
+        /// `(path, unavailable)` of the merged repos, sorted
+        expected: Vec<(&'a str, bool)>,
+    }
+
+    #[test]
+    fn merge_scan_cases() {
+        let elsewhere = tempfile::tempdir().unwrap();
+        std::fs::create_dir(elsewhere.path().join(".git")).unwrap();
+        let elsewhere = elsewhere.path().to_string_lossy().to_string();
+        let online = volume(Some(BASE));
+
+        let cases = [
+            Case {
+                name: "new repos are added",
+                stored: vec![],
+                scanned: vec![test_repo("/Volumes/PSSD/app")],
+                volumes: online.clone(),
+                expected: vec![("/Volumes/PSSD/app", false)],
+            },
+            Case {
+                name: "repos that are gone are dropped",
+                stored: vec![
+                    customized("/Volumes/PSSD/app"),
+                    test_repo("/Volumes/PSSD/old"),
+                ],
+                scanned: vec![test_repo("/Volumes/PSSD/app")],
+                volumes: online.clone(),
+                expected: vec![("/Volumes/PSSD/app", false)],
+            },
+            Case {
+                name: "repos on an offline volume stay unavailable",
+                stored: vec![customized("/Volumes/PSSD/app")],
+                scanned: vec![],
+                volumes: volume(None),
+                expected: vec![("/Volumes/PSSD/app", true)],
+            },
+            Case {
+                name: "repos follow their volume to a new mount",
+                stored: vec![customized("/Volumes/PSSD/app")],
+                scanned: vec![test_repo("/Volumes/PSSD 1/app")],
+                volumes: volume(Some("/Volumes/PSSD 1")),
+                expected: vec![("/Volumes/PSSD 1/app", false)],
+            },
+            Case {
+                name: "imported repos stay while they exist",
+                stored: vec![imported(&elsewhere), imported("/missing/app")],
+                scanned: vec![],
+                volumes: online.clone(),
+                expected: vec![(elsewhere.as_str(), false)],
+            },
+        ];
+
+        for case in cases {
+            let merged = merge_scan(
+                case.stored.into_iter().collect(),
+                case.scanned,
+                &case.volumes,
+            );
+            let mut paths: Vec<(&str, bool)> = merged
+                .iter()
+                .map(|repo| (repo.path.as_str(), repo.unavailable))
+                .collect();
+            paths.sort();
+            assert_eq!(paths, case.expected, "{}", case.name);
+
+            // whatever happened to the path, what the user set is kept
+            for repo in merged.iter().filter(|repo| repo.count > 0) {
+                assert_eq!(repo.title(), "App", "{}", case.name);
+                assert!(repo.archived, "{}", case.name);
+            }
+        }
+    }
+
+    #[test]
+    fn a_rescan_updates_scanned_fields() {
+        let mut scanned = test_repo("/Volumes/PSSD/app");
+        scanned.remote = Some("github.com/me/app".to_string());
+        let mut stored = customized("/Volumes/PSSD/app");
+        stored.unavailable = true;
+
+        let merged = merge_scan([stored].into(), vec![scanned], &volume(Some(BASE)));
+        let repo = merged.iter().next().unwrap();
+        assert_eq!(repo.remote.as_deref(), Some("github.com/me/app"));
+        assert!(!repo.unavailable);
+        assert_eq!((repo.title(), repo.count), ("App", 3));
+    }
+}
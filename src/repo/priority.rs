@@ -0,0 +1,59 @@
+use crate::config::{GitLauncherAnalysisConfig, IoPriority};
+
+/// run `f` on a fresh thread with the cpu and io priority of `config`, so the
+/// lowered priority doesn't stick to a pooled thread that runs other work later
+pub fn with_priority<T: Send>(
+    config: &GitLauncherAnalysisConfig,
+    f: impl FnOnce() -> T + Send,
+) -> T {
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                lower_priority(config.nice, config.io_priority);
+                f()
+            })
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+/// macOS has no per-thread nice value, a quality of service class lowers cpu
+/// and io priority of the thread together
+#[cfg(target_os = "macos")]
+fn lower_priority(nice: i32, io_priority: IoPriority) {
+    use libc::qos_class_t::{QOS_CLASS_BACKGROUND, QOS_CLASS_UTILITY};
+
+    let class = match io_priority {
+        IoPriority::Idle => QOS_CLASS_BACKGROUND,
+        _ if nice > 0 || io_priority == IoPriority::Low => QOS_CLASS_UTILITY,
+        _ => return,
+    };
+    unsafe {
+        libc::pthread_set_qos_class_self_np(class, 0);
+    }
+}
+
+/// linux applies `setpriority` and `ioprio_set` to the calling thread only
+#[cfg(target_os = "linux")]
+fn lower_priority(nice: i32, io_priority: IoPriority) {
+    const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+    const IOPRIO_CLASS_SHIFT: libc::c_long = 13;
+    const IOPRIO_CLASS_BE: libc::c_long = 2;
+    const IOPRIO_CLASS_IDLE: libc::c_long = 3;
+
+    unsafe {
+        if nice > 0 {
+            libc::setpriority(libc::PRIO_PROCESS, 0, nice.min(19));
+        }
+        let priority = match io_priority {
+            IoPriority::Normal => return,
+            // lowest level of the best-effort class
+            IoPriority::Low => (IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT) | 7,
+            IoPriority::Idle => IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+        };
+        libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, priority);
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn lower_priority(_nice: i32, _io_priority: IoPriority) {}
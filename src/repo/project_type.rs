@@ -0,0 +1,165 @@
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+/// kind of project, detected from the build manifests at the repo root
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectType {
+    Cargo,
+    Npm,
+    Pnpm,
+    Yarn,
+    Go,
+    Gradle,
+    Maven,
+    Poetry,
+    Uv,
+    Setuptools,
+    Python,
+    CMake,
+    Flutter,
+    Dart,
+    Zig,
+    Nix,
+}
+
+impl ProjectType {
+    /// key used in `type:` filters
+    pub fn key(&self) -> &'static str {
+        match self {
+            ProjectType::Cargo => "cargo",
+            ProjectType::Npm => "npm",
+            ProjectType::Pnpm => "pnpm",
+            ProjectType::Yarn => "yarn",
+            ProjectType::Go => "go",
+            ProjectType::Gradle => "gradle",
+            ProjectType::Maven => "maven",
+            ProjectType::Poetry => "poetry",
+            ProjectType::Uv => "uv",
+            ProjectType::Setuptools => "setuptools",
+            ProjectType::Python => "python",
+            ProjectType::CMake => "cmake",
+            ProjectType::Flutter => "flutter",
+            ProjectType::Dart => "dart",
+            ProjectType::Zig => "zig",
+            ProjectType::Nix => "nix",
+        }
+    }
+
+    /// shared key of related project types, e.g. `node` for npm, pnpm and yarn
+    pub fn family(&self) -> Option<&'static str> {
+        match self {
+            ProjectType::Npm | ProjectType::Pnpm | ProjectType::Yarn => Some("node"),
+            ProjectType::Poetry | ProjectType::Uv | ProjectType::Setuptools => Some("python"),
+            ProjectType::Gradle | ProjectType::Maven => Some("jvm"),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProjectType::Cargo => "Cargo",
+            ProjectType::Npm => "npm",
+            ProjectType::Pnpm => "pnpm",
+            ProjectType::Yarn => "Yarn",
+            ProjectType::Go => "Go",
+            ProjectType::Gradle => "Gradle",
+            ProjectType::Maven => "Maven",
+            ProjectType::Poetry => "Poetry",
+            ProjectType::Uv => "uv",
+            ProjectType::Setuptools => "setuptools",
+            ProjectType::Python => "Python",
+            ProjectType::CMake => "CMake",
+            ProjectType::Flutter => "Flutter",
+            ProjectType::Dart => "Dart",
+            ProjectType::Zig => "Zig",
+            ProjectType::Nix => "Nix",
+        }
+    }
+
+    /// whether a `type:` filter value selects this project type
+    pub fn matches(&self, filter: &str) -> bool {
+        self.key() == filter || self.family() == Some(filter)
+    }
+
+    /// detect project types from manifest files, only looks at the repo root
+    pub fn detect(repo_path: &Path) -> Vec<ProjectType> {
+        let has = |name: &str| repo_path.join(name).exists();
+        let read = |name: &str| fs::read_to_string(repo_path.join(name)).unwrap_or_default();
+
+        let mut types = Vec::new();
+
+        if has("Cargo.toml") {
+            types.push(ProjectType::Cargo);
+        }
+
+        if has("package.json") {
+            types.push(if has("pnpm-lock.yaml") || has("pnpm-workspace.yaml") {
+                ProjectType::Pnpm
+            } else if has("yarn.lock") || has(".yarnrc.yml") {
+                ProjectType::Yarn
+            } else {
+                ProjectType::Npm
+            });
+        }
+
+        if has("go.mod") || has("go.work") {
+            types.push(ProjectType::Go);
+        }
+
+        if [
+            "build.gradle",
+            "build.gradle.kts",
+            "settings.gradle",
+            "settings.gradle.kts",
+        ]
+        .iter()
+        .any(|name| has(name))
+        {
+            types.push(ProjectType::Gradle);
+        }
+
+        if has("pom.xml") {
+            types.push(ProjectType::Maven);
+        }
+
+        if has("pyproject.toml") || has("setup.py") || has("setup.cfg") {
+            let pyproject = read("pyproject.toml");
+            types.push(
+                if pyproject.contains("[tool.poetry") || has("poetry.lock") {
+                    ProjectType::Poetry
+                } else if has("uv.lock") || pyproject.contains("[tool.uv") {
+                    ProjectType::Uv
+                } else if has("setup.py") || has("setup.cfg") || pyproject.contains("setuptools") {
+                    ProjectType::Setuptools
+                } else {
+                    ProjectType::Python
+                },
+            );
+        }
+
+        if has("CMakeLists.txt") {
+            types.push(ProjectType::CMake);
+        }
+
+        if has("pubspec.yaml") {
+            let pubspec = read("pubspec.yaml");
+            types.push(if pubspec.contains("flutter:") {
+                ProjectType::Flutter
+            } else {
+                ProjectType::Dart
+            });
+        }
+
+        if has("build.zig") || has("build.zig.zon") {
+            types.push(ProjectType::Zig);
+        }
+
+        if has("flake.nix") {
+            types.push(ProjectType::Nix);
+        }
+
+        types
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::HashSet;
+
+use crate::config::GitLauncherRankingConfig;
+
+use super::{Repo, SIGNIFICANT_SHARE, now_secs};
+
+/// result order, frecency unless a `sort:` filter is given
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortKey {
+    #[default]
+    Frecency,
+    /// `sort:size`, total bytes on disk
+    Size,
+    /// `sort:worktree`
+    Worktree,
+    /// `sort:git`
+    Git,
+    /// `sort:artifacts`, build output like `target` or `node_modules`
+    Artifacts,
+}
+
+impl SortKey {
+    pub fn parse(key: &str) -> Option<Self> {
+        match key {
+            "frecency" => Some(SortKey::Frecency),
+            "size" | "total" => Some(SortKey::Size),
+            "worktree" => Some(SortKey::Worktree),
+            "git" => Some(SortKey::Git),
+            "artifacts" | "build" => Some(SortKey::Artifacts),
+            _ => None,
+        }
+    }
+
+    /// the sort value of `repo`, `None` for frecency or unmeasured repos
+    pub fn bytes(&self, repo: &Repo) -> Option<u64> {
+        let usage = repo.disk_usage.as_ref()?;
+        match self {
+            SortKey::Frecency => None,
+            SortKey::Size => Some(usage.total()),
+            SortKey::Worktree => Some(usage.worktree),
+            SortKey::Git => Some(usage.git),
+            SortKey::Artifacts => Some(usage.artifacts),
+        }
+    }
+}
+
+/// a parsed search input, free text plus `key:value` filters
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    pub text: String,
+    /// `is:archived`, only match archived repos
+    pub archived: bool,
+    /// `tag:<tag>`, repos must carry every listed tag
+    pub tags: Vec<String>,
+    /// `type:<type>`, e.g. `type:cargo` or `type:node`
+    pub project_types: Vec<String>,
+    /// `lang:<language>`, the language must be a significant share of the
+    /// code, not necessarily the top one
+    pub languages: Vec<String>,
+    /// `host:<host>`, the host of the remote, e.g. `host:github.com`
+    pub hosts: Vec<String>,
+    /// `sort:<key>`, see [`SortKey`]
+    pub sort: SortKey,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Self {
+        let mut query = Query::default();
+        let mut text = Vec::new();
+
+        for token in input.split_whitespace() {
+            match token.split_once(':') {
+                Some(("is", "archived")) => query.archived = true,
+                Some(("tag", tag)) if !tag.is_empty() => query.tags.push(tag.to_lowercase()),
+                Some(("type", kind)) if !kind.is_empty() => {
+                    query.project_types.push(kind.to_lowercase())
+                }
+                Some(("lang", language)) if !language.is_empty() => {
+                    query.languages.push(language.to_string())
+                }
+                Some(("host", host)) if !host.is_empty() => query.hosts.push(host.to_lowercase()),
+                Some(("sort", key)) => match SortKey::parse(key) {
+                    Some(sort) => query.sort = sort,
+                    None => text.push(token),
+                },
+                _ => text.push(token),
+            }
+        }
+
+        query.text = text.join(" ");
+        query
+    }
+
+    /// every filter except the text, which [`search_repos`] scores
+    pub fn matches(&self, repo: &Repo) -> bool {
+        repo.archived == self.archived
+            && self.tags.iter().all(|tag| {
+                repo.tags()
+                    .iter()
+                    .any(|repo_tag| repo_tag.to_lowercase() == *tag)
+            })
+            && self
+                .project_types
+                .iter()
+                .all(|filter| repo.project_types.iter().any(|kind| kind.matches(filter)))
+            && self
+                .languages
+                .iter()
+                .all(|language| repo.language_share(language) >= SIGNIFICANT_SHARE)
+            && (self.hosts.is_empty() || repo.host().is_some_and(|host| self.hosts.contains(&host)))
+    }
+}
+
+/// filter repos by the query and rank them by how well the text matches, then
+/// by frecency, or by size for `sort:` queries
+pub fn search_repos(
+    repos: &HashSet<Repo>,
+    query: &Query,
+    ranking: &GitLauncherRankingConfig,
+) -> Vec<Repo> {
+    let now = now_secs();
+    let mut result: Vec<(i64, f64, &Repo)> = repos
+        .iter()
+        .filter(|repo| query.matches(repo))
+        .filter_map(|repo| {
+            let found = repo.fuzzy_score(&query.text)?;
+            Some((found, repo.frecency(now, ranking), repo))
+        })
+        .collect();
+
+    result.sort_by(|(a_found, a_score, a), (b_found, b_score, b)| {
+        query
+            .sort
+            .bytes(b)
+            .cmp(&query.sort.bytes(a))
+            .then_with(|| b_found.cmp(a_found))
+            .then_with(|| b_score.total_cmp(a_score))
+            .then_with(|| a.title().cmp(b.title()))
+    });
+
+    result
+        .into_iter()
+        .map(|(_, _, repo)| repo.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo::test_repo;
+
+    fn search(repos: &HashSet<Repo>, input: &str) -> Vec<String> {
+        search_repos(
+            repos,
+            &Query::parse(input),
+            &GitLauncherRankingConfig::default(),
+        )
+        .into_iter()
+        .map(|repo| repo.path)
+        .collect()
+    }
+
+    #[test]
+    fn archived_repos_only_match_is_archived() {
+        let mut archived = test_repo("/old");
+        archived.archived = true;
+        let mut repos: HashSet<Repo> = [test_repo("/app"), archived].into_iter().collect();
+
+        assert_eq!(search(&repos, ""), ["/app"]);
+        assert_eq!(search(&repos, "old"), Vec::<String>::new());
+        assert_eq!(search(&repos, "is:archived"), ["/old"]);
+        assert_eq!(search(&repos, "is:archived old"), ["/old"]);
+
+        // a rescan keeps the flag, unarchiving brings the repo back
+        let mut old = repos.take(&test_repo("/old")).unwrap();
+        old.update_from_scan(test_repo("/old"));
+        assert!(old.archived);
+        old.archived = false;
+        repos.insert(old);
+        assert_eq!(search(&repos, "old"), ["/old"]);
+        assert_eq!(search(&repos, "is:archived"), Vec::<String>::new());
+    }
+}
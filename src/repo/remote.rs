@@ -0,0 +1,115 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// the `.git` directory of a repo, following the `gitdir:` file of worktrees
+/// and submodules
+pub fn git_dir(repo_path: &Path) -> Option<PathBuf> {
+    let dot_git = repo_path.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let content = fs::read_to_string(&dot_git).ok()?;
+    let dir = content.strip_prefix("gitdir:")?.trim();
+    Some(repo_path.join(dir))
+}
+
+/// url of the `origin` remote, or of the first remote when there is no origin
+pub fn remote_url(repo_path: &Path) -> Option<String> {
+    let git_dir = git_dir(repo_path)?;
+    // worktrees keep their config in the main repo
+    let common_dir = match fs::read_to_string(git_dir.join("commondir")) {
+        Ok(common_dir) => git_dir.join(common_dir.trim()),
+        Err(_) => git_dir,
+    };
+    let config = fs::read_to_string(common_dir.join("config")).ok()?;
+
+    let mut remote = None;
+    let mut first = None;
+    let mut origin = None;
+    for line in config.lines().map(str::trim) {
+        if line.starts_with('[') {
+            remote = line
+                .strip_prefix("[remote \"")
+                .and_then(|rest| rest.strip_suffix("\"]"))
+                .map(str::to_string);
+            continue;
+        }
+        let Some(name) = &remote else {
+            continue;
+        };
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if key.trim() != "url" {
+            continue;
+        }
+        let url = value.trim().to_string();
+        if name == "origin" {
+            origin.get_or_insert(url);
+        } else {
+            first.get_or_insert(url);
+        }
+    }
+
+    origin.or(first)
+}
+
+/// a remote url reduced to `host/owner/repo`, so ssh and https urls of the
+/// same repo compare equal
+pub fn normalize_remote(url: &str) -> String {
+    let url = url.trim();
+    let url = match url.split_once("://") {
+        // `scheme://user@host:port/owner/repo`, the port is dropped
+        Some((_, rest)) => {
+            let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+            let host = authority
+                .rsplit_once('@')
+                .map_or(authority, |(_, host)| host);
+            let host = host.split_once(':').map_or(host, |(host, _)| host);
+            format!("{host}/{path}")
+        }
+        // scp-like `user@host:owner/repo`, everything after the `:` is the
+        // path, even when it starts with a digit
+        None => {
+            let url = url.split_once('@').map_or(url, |(_, rest)| rest);
+            url.replacen(':', "/", 1)
+        }
+    };
+    url.trim_end_matches('/')
+        .trim_end_matches(".git")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_remote_urls() {
+        for (url, expected) in [
+            ("git@github.com:owner/repo.git", "github.com/owner/repo"),
+            ("https://github.com/owner/repo.git", "github.com/owner/repo"),
+            ("https://github.com/Owner/Repo/", "github.com/owner/repo"),
+            (
+                "ssh://git@example.com:2222/owner/repo.git",
+                "example.com/owner/repo",
+            ),
+            (
+                "https://user@example.com:8443/owner/repo",
+                "example.com/owner/repo",
+            ),
+            (
+                "git@github.com:1password/onepassword-sdk-go.git",
+                "github.com/1password/onepassword-sdk-go",
+            ),
+            (
+                "https://github.com/1password/onepassword-sdk-go",
+                "github.com/1password/onepassword-sdk-go",
+            ),
+        ] {
+            assert_eq!(normalize_remote(url), expected, "{url}");
+        }
+    }
+}
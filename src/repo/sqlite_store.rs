@@ -0,0 +1,292 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::Mutex,
+};
+
+use rusqlite::{Connection, OptionalExtension, Transaction, TransactionBehavior, params};
+
+use crate::config::{REPO_PATH, SQLITE_PATH};
+
+use super::{JsonRepoStore, Repo, RepoStorage, StoreLoad, now_secs};
+
+/// `kind` of the language and activity analysis in the `analysis` table
+const ANALYSIS_KIND: &str = "analysis";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS repos (
+    path TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    language TEXT NOT NULL,
+    count INTEGER NOT NULL DEFAULT 0,
+    data TEXT NOT NULL
+);
+-- repos are searched in memory, nothing looks rows up by name or language
+DROP INDEX IF EXISTS repos_name;
+DROP INDEX IF EXISTS repos_language;
+
+CREATE TABLE IF NOT EXISTS opens (
+    repo_path TEXT NOT NULL REFERENCES repos(path) ON DELETE CASCADE,
+    timestamp INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS opens_repo ON opens(repo_path, timestamp);
+
+-- tags come from the committed `.git-launcher.toml` and live in `data`
+DROP TABLE IF EXISTS tags;
+
+CREATE TABLE IF NOT EXISTS analysis (
+    repo_path TEXT NOT NULL REFERENCES repos(path) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    cache_key TEXT NOT NULL,
+    data TEXT NOT NULL,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (repo_path, kind)
+);
+";
+
+/// repo index in an embedded sqlite database
+///
+/// every repo row keeps its json in `data` so new `Repo` fields don't need a
+/// schema change, the other columns are there to read the database by hand. open
+/// timestamps live in `opens` and cached analyses in `analysis`, saves only
+/// write the rows of repos that changed
+pub struct SqliteRepoStore {
+    conn: Mutex<Connection>,
+    /// `PRAGMA data_version` as last seen, it only changes on commits made
+    /// by other connections
+    data_version: Mutex<i64>,
+}
+
+impl SqliteRepoStore {
+    /// open `~/.git-launcher/repo.db`, importing `repo.json` on first use
+    pub fn open_default() -> anyhow::Result<Self> {
+        let store = Self::open(SQLITE_PATH.as_path())?;
+        if store.is_empty()? {
+            let imported = store.import_json(&JsonRepoStore::with_path(REPO_PATH.clone()))?;
+            if imported > 0 {
+                eprintln!("imported {} repos from repo.json", imported);
+            }
+        }
+        Ok(store)
+    }
+
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "foreign_keys", "ON")?;
+        conn.pragma_update(None, "busy_timeout", 5000)?;
+        conn.execute_batch(SCHEMA)?;
+        let data_version = data_version(&conn)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            data_version: Mutex::new(data_version),
+        })
+    }
+
+    fn is_empty(&self) -> anyhow::Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM repos", [], |row| row.get(0))?;
+        Ok(count == 0)
+    }
+
+    /// copy every repo of a json store into the database
+    pub fn import_json(&self, json: &JsonRepoStore) -> anyhow::Result<usize> {
+        let repos = json.load()?.repos;
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for repo in &repos {
+            upsert(&tx, repo)?;
+        }
+        tx.commit()?;
+        Ok(repos.len())
+    }
+}
+
+/// every stored repo with its `opens` filled in from the opens table
+fn read_repos(conn: &Connection) -> anyhow::Result<Vec<Repo>> {
+    let mut opens: HashMap<String, Vec<u64>> = HashMap::new();
+    let mut stmt = conn.prepare("SELECT repo_path, timestamp FROM opens ORDER BY timestamp")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+    })?;
+    for row in rows {
+        let (path, timestamp) = row?;
+        opens.entry(path).or_default().push(timestamp as u64);
+    }
+
+    let mut stmt = conn.prepare("SELECT data FROM repos")?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+    let mut repos = Vec::new();
+    for data in rows {
+        match serde_json::from_str::<Repo>(&data?) {
+            Ok(mut repo) => {
+                repo.opens = opens.remove(&repo.path).unwrap_or_default();
+                repos.push(repo);
+            }
+            Err(err) => eprintln!("skipping invalid repo row: {}", err),
+        }
+    }
+    Ok(repos)
+}
+
+/// write the repos that differ from `before` and delete the ones that are
+/// gone, `before` being what the database held when the transaction started
+fn write_changes(tx: &Transaction, before: &[Repo], repos: &[&Repo]) -> anyhow::Result<()> {
+    let before: HashMap<&str, String> = before
+        .iter()
+        .map(|repo| Ok((repo.path.as_str(), serde_json::to_string(repo)?)))
+        .collect::<anyhow::Result<_>>()?;
+
+    for repo in repos {
+        if before.get(repo.path.as_str()) != Some(&serde_json::to_string(repo)?) {
+            upsert(tx, repo)?;
+        }
+    }
+
+    let kept: HashSet<&str> = repos.iter().map(|repo| repo.path.as_str()).collect();
+    let mut delete = tx.prepare_cached("DELETE FROM repos WHERE path = ?1")?;
+    for path in before.keys().filter(|path| !kept.contains(*path)) {
+        delete.execute(params![path])?;
+    }
+    Ok(())
+}
+
+fn data_version(conn: &Connection) -> rusqlite::Result<i64> {
+    conn.query_row("PRAGMA data_version", [], |row| row.get(0))
+}
+
+fn upsert(tx: &Transaction, repo: &Repo) -> anyhow::Result<()> {
+    // the opens table holds the timestamps
+    let data = serde_json::to_string(&Repo {
+        opens: Vec::new(),
+        ..repo.clone()
+    })?;
+    tx.execute(
+        "INSERT INTO repos (path, name, language, count, data) VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(path) DO UPDATE SET
+            name = excluded.name,
+            language = excluded.language,
+            count = excluded.count,
+            data = excluded.data",
+        params![repo.path, repo.name, repo.language, repo.count, data],
+    )?;
+
+    tx.execute("DELETE FROM opens WHERE repo_path = ?1", params![repo.path])?;
+    let mut insert =
+        tx.prepare_cached("INSERT INTO opens (repo_path, timestamp) VALUES (?1, ?2)")?;
+    for timestamp in &repo.opens {
+        insert.execute(params![repo.path, *timestamp as i64])?;
+    }
+
+    Ok(())
+}
+
+impl RepoStorage for SqliteRepoStore {
+    fn load(&self) -> anyhow::Result<StoreLoad> {
+        let conn = self.conn.lock().unwrap();
+        let repos = read_repos(&conn)?;
+        Ok(StoreLoad {
+            needs_scan: repos.is_empty(),
+            repos,
+        })
+    }
+
+    fn save(&self, repos: &[&Repo]) -> anyhow::Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let before = read_repos(&tx)?;
+        write_changes(&tx, &before, repos)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// an immediate transaction takes the write lock before reading, so no
+    /// other connection commits between the read and the write
+    fn transact(&self, f: &mut dyn FnMut(&mut Vec<Repo>)) -> anyhow::Result<Vec<Repo>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let before = read_repos(&tx)?;
+        let mut repos = before.clone();
+        f(&mut repos);
+        write_changes(&tx, &before, &repos.iter().collect::<Vec<_>>())?;
+        tx.commit()?;
+        Ok(repos)
+    }
+
+    fn read_analysis(&self, path: &str) -> Option<String> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT data FROM analysis WHERE repo_path = ?1 AND kind = ?2",
+            params![path, ANALYSIS_KIND],
+            |row| row.get(0),
+        )
+        .optional()
+        .ok()
+        .flatten()
+    }
+
+    /// rows belong to an indexed repo and go away with it
+    fn write_analysis(&self, path: &str, key: &str, entry: &str) -> anyhow::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO analysis (repo_path, kind, cache_key, data, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(repo_path, kind) DO UPDATE SET
+                cache_key = excluded.cache_key,
+                data = excluded.data,
+                updated_at = excluded.updated_at",
+            params![path, ANALYSIS_KIND, key, entry, now_secs() as i64],
+        )?;
+        Ok(())
+    }
+
+    fn poll_changed(&self) -> bool {
+        let conn = self.conn.lock().unwrap();
+        let Ok(current) = data_version(&conn) else {
+            return false;
+        };
+        let mut seen = self.data_version.lock().unwrap();
+        if *seen == current {
+            return false;
+        }
+        *seen = current;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo::test_repo;
+
+    #[test]
+    fn opens_and_analysis_live_in_their_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SqliteRepoStore::open(&dir.path().join("repo.db")).unwrap();
+        store.save(&[&test_repo("/a"), &test_repo("/b")]).unwrap();
+        store
+            .transact(&mut |repos| {
+                for repo in repos.iter_mut().filter(|repo| repo.path == "/a") {
+                    repo.opens = vec![1, 2];
+                }
+            })
+            .unwrap();
+        store.write_analysis("/a", "key", "{}").unwrap();
+
+        let conn = store.conn.lock().unwrap();
+        let opens: i64 = conn
+            .query_row("SELECT COUNT(*) FROM opens", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(opens, 2);
+        drop(conn);
+        let repos = store.load().unwrap().repos;
+        let a = repos.iter().find(|repo| repo.path == "/a").unwrap();
+        assert_eq!(a.opens, [1, 2]);
+        assert_eq!(store.read_analysis("/a").as_deref(), Some("{}"));
+
+        // dropping a repo drops its opens and analysis
+        store.save(&[&test_repo("/b")]).unwrap();
+        assert_eq!(store.read_analysis("/a"), None);
+        assert_eq!(store.load().unwrap().repos.len(), 1);
+    }
+}
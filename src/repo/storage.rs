@@ -0,0 +1,76 @@
+use std::{fs, sync::OnceLock};
+
+use crate::{
+    config::{GitLauncherStorageConfig, StorageBackend},
+    state_file,
+};
+
+use super::{JsonRepoStore, Repo, StoreLoad, analysis_file};
+
+/// a place the repo index is persisted to
+pub trait RepoStorage: Send + Sync {
+    fn load(&self) -> anyhow::Result<StoreLoad>;
+
+    fn save(&self, repos: &[&Repo]) -> anyhow::Result<()>;
+
+    /// load the stored repos, apply `f` and save the result as one step, so
+    /// changes other processes saved since our last load are kept. returns
+    /// the repos as saved
+    fn transact(&self, f: &mut dyn FnMut(&mut Vec<Repo>)) -> anyhow::Result<Vec<Repo>> {
+        let mut repos = self.load()?.repos;
+        f(&mut repos);
+        self.save(&repos.iter().collect::<Vec<_>>())?;
+        Ok(repos)
+    }
+
+    /// the cached analysis entry of the repo at `path` as json, by default
+    /// one file per repo under `cache/analysis`
+    fn read_analysis(&self, path: &str) -> Option<String> {
+        fs::read_to_string(analysis_file(path)).ok()
+    }
+
+    /// cache the analysis entry of the repo at `path`, `key` identifies the
+    /// state of the repo it was made from
+    fn write_analysis(&self, path: &str, key: &str, entry: &str) -> anyhow::Result<()> {
+        let _ = key;
+        let file = analysis_file(path);
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        state_file::write(&file, entry.as_bytes())?;
+        Ok(())
+    }
+
+    /// whether another process changed the store since we last loaded or
+    /// saved it, callers should `load` again when this returns true
+    fn poll_changed(&self) -> bool {
+        false
+    }
+}
+
+static STORAGE: OnceLock<Box<dyn RepoStorage>> = OnceLock::new();
+
+/// select the storage backend, must run before the first `storage()` call
+pub fn init_storage(config: &GitLauncherStorageConfig) -> anyhow::Result<()> {
+    let storage: Box<dyn RepoStorage> = match config.backend {
+        StorageBackend::Json => Box::new(JsonRepoStore::new()),
+        #[cfg(feature = "sqlite")]
+        StorageBackend::Sqlite => Box::new(super::SqliteRepoStore::open_default()?),
+        #[cfg(not(feature = "sqlite"))]
+        StorageBackend::Sqlite => {
+            eprintln!("sqlite support is not compiled in, falling back to repo.json");
+            Box::new(JsonRepoStore::new())
+        }
+    };
+
+    STORAGE
+        .set(storage)
+        .map_err(|_| anyhow::anyhow!("repo storage is already initialized"))
+}
+
+/// the configured storage backend, `repo.json` if none was selected
+pub fn storage() -> &'static dyn RepoStorage {
+    STORAGE
+        .get_or_init(|| Box::new(JsonRepoStore::new()))
+        .as_ref()
+}
@@ -0,0 +1,414 @@
+use std::{fs, path::PathBuf, sync::Mutex};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    config::REPO_PATH,
+    state_file::{self, FileStamp, StateLock},
+};
+
+use super::{Repo, RepoStorage, now_secs};
+
+/// current schema version of `repo.json`
+pub const STORE_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct StoreFile<'a> {
+    version: u32,
+    repos: Vec<&'a Repo>,
+}
+
+#[derive(Deserialize)]
+struct RawStoreFile {
+    version: u32,
+    #[serde(default)]
+    repos: Vec<Value>,
+}
+
+/// result of loading the store
+#[derive(Debug, Default)]
+pub struct StoreLoad {
+    pub repos: Vec<Repo>,
+    /// the store was empty, missing or unreadable, a full scan is needed
+    pub needs_scan: bool,
+}
+
+/// versioned on-disk repo index with a backup of the last good file
+pub struct JsonRepoStore {
+    path: PathBuf,
+    /// stamp of the file as we last read or wrote it
+    seen: Mutex<Option<FileStamp>>,
+}
+
+impl Default for JsonRepoStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JsonRepoStore {
+    pub fn new() -> Self {
+        Self::with_path(REPO_PATH.clone())
+    }
+
+    pub fn with_path<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            seen: Mutex::new(None),
+        }
+    }
+
+    fn sibling(&self, suffix: &str) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(suffix);
+        PathBuf::from(name)
+    }
+
+    fn backup_path(&self) -> PathBuf {
+        self.sibling(".bak")
+    }
+
+    /// load the store, migrating old schemas and falling back to the backup
+    /// (or an empty index that needs a rescan) when the file is corrupt
+    pub fn load(&self) -> anyhow::Result<StoreLoad> {
+        let content = match state_file::read(&self.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err.into()),
+        };
+
+        *self.seen.lock().unwrap() = FileStamp::of(&self.path);
+
+        if content.trim().is_empty() {
+            return Ok(StoreLoad {
+                repos: Vec::new(),
+                needs_scan: true,
+            });
+        }
+
+        match Self::parse(&content) {
+            Ok(repos) => {
+                // this file is known to be good, keep it around
+                if let Err(err) = state_file::write(&self.backup_path(), content.as_bytes()) {
+                    eprintln!("failed to back up repo store: {}", err);
+                }
+                Ok(StoreLoad {
+                    repos,
+                    needs_scan: false,
+                })
+            }
+            Err(err) => {
+                let _lock = StateLock::exclusive(&self.path)?;
+                // another process may have replaced the file since we read it
+                if let Ok(repos) = fs::read_to_string(&self.path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|content| Self::parse(&content))
+                {
+                    *self.seen.lock().unwrap() = FileStamp::of(&self.path);
+                    return Ok(StoreLoad {
+                        repos,
+                        needs_scan: false,
+                    });
+                }
+
+                eprintln!("repo store is corrupt: {}", err);
+                let corrupt = self.sibling(&format!(".corrupt-{}", now_secs()));
+                fs::rename(&self.path, &corrupt)?;
+                Ok(self.load_backup())
+            }
+        }
+    }
+
+    /// refuse to overwrite a store written by a newer version, it would be
+    /// rewritten as `STORE_VERSION` and lose whatever the newer schema added
+    fn check_writable(content: &str) -> anyhow::Result<()> {
+        let version = serde_json::from_str::<Value>(content)
+            .ok()
+            .and_then(|value| value.get("version").and_then(Value::as_u64));
+        match version {
+            Some(version) if version > STORE_VERSION as u64 => anyhow::bail!(
+                "repo store version {} is newer than supported version {}, not writing it",
+                version,
+                STORE_VERSION
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// write `repos` over the store, the caller holds the exclusive lock
+    fn replace(&self, mut repos: Vec<&Repo>) -> anyhow::Result<()> {
+        repos.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let content = serde_json::to_string(&StoreFile {
+            version: STORE_VERSION,
+            repos,
+        })?;
+        state_file::replace(&self.path, content.as_bytes())?;
+        *self.seen.lock().unwrap() = FileStamp::of(&self.path);
+        Ok(())
+    }
+
+    fn load_backup(&self) -> StoreLoad {
+        let repos = state_file::read(&self.backup_path())
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Self::parse(&content));
+
+        match repos {
+            // the backup may be missing recent repos, rescan to catch up
+            Ok(repos) => StoreLoad {
+                repos,
+                needs_scan: true,
+            },
+            Err(_) => StoreLoad {
+                repos: Vec::new(),
+                needs_scan: true,
+            },
+        }
+    }
+
+    fn parse(content: &str) -> anyhow::Result<Vec<Repo>> {
+        let value: Value = serde_json::from_str(content)?;
+        let file = migrate(value)?;
+
+        if file.version > STORE_VERSION {
+            eprintln!(
+                "repo store version {} is newer than supported version {}, it is read-only",
+                file.version, STORE_VERSION
+            );
+        }
+
+        // a single bad entry should not take the whole index down
+        Ok(file
+            .repos
+            .into_iter()
+            .filter_map(|repo| match serde_json::from_value(repo) {
+                Ok(repo) => Some(repo),
+                Err(err) => {
+                    eprintln!("skipping invalid repo entry: {}", err);
+                    None
+                }
+            })
+            .collect())
+    }
+}
+
+impl RepoStorage for JsonRepoStore {
+    fn load(&self) -> anyhow::Result<StoreLoad> {
+        JsonRepoStore::load(self)
+    }
+
+    fn save(&self, repos: &[&Repo]) -> anyhow::Result<()> {
+        let _lock = StateLock::exclusive(&self.path)?;
+        if let Ok(content) = fs::read_to_string(&self.path) {
+            Self::check_writable(&content)?;
+        }
+        self.replace(repos.to_vec())
+    }
+
+    /// holds the lock from reading the file to writing it back, a store that
+    /// can't be parsed is left alone for `load` to recover
+    fn transact(&self, f: &mut dyn FnMut(&mut Vec<Repo>)) -> anyhow::Result<Vec<Repo>> {
+        let _lock = StateLock::exclusive(&self.path)?;
+        let mut repos = match fs::read_to_string(&self.path) {
+            Ok(content) if content.trim().is_empty() => Vec::new(),
+            Ok(content) => {
+                Self::check_writable(&content)?;
+                Self::parse(&content)?
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err.into()),
+        };
+        f(&mut repos);
+        self.replace(repos.iter().collect())?;
+        Ok(repos)
+    }
+
+    fn poll_changed(&self) -> bool {
+        let current = FileStamp::of(&self.path);
+        let mut seen = self.seen.lock().unwrap();
+        if *seen == current {
+            return false;
+        }
+        *seen = current;
+        true
+    }
+}
+
+/// run the migration chain until the file is at `STORE_VERSION`
+fn migrate(mut value: Value) -> anyhow::Result<RawStoreFile> {
+    loop {
+        let version = match &value {
+            Value::Array(_) => 0,
+            Value::Object(map) => map
+                .get("version")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| anyhow::anyhow!("repo store has no version"))?
+                as u32,
+            _ => anyhow::bail!("repo store has an unexpected shape"),
+        };
+
+        if version >= STORE_VERSION {
+            return Ok(serde_json::from_value(value)?);
+        }
+
+        value = match version {
+            0 => migrate_v0(value),
+            _ => unreachable!(),
+        };
+    }
+}
+
+/// v0 was a bare `Vec<Repo>`
+fn migrate_v0(value: Value) -> Value {
+    serde_json::json!({
+        "version": 1,
+        "repos": value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::repo::test_repo;
+
+    fn find<'a>(repos: &'a [Repo], path: &str) -> &'a Repo {
+        repos.iter().find(|repo| repo.path == path).unwrap()
+    }
+
+    fn store(dir: &Path) -> JsonRepoStore {
+        JsonRepoStore::with_path(dir.join("repo.json"))
+    }
+
+    fn files(dir: &Path) -> Vec<String> {
+        let mut files: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|name| !name.ends_with(".lock"))
+            .collect();
+        files.sort();
+        files
+    }
+
+    const V0: &str = "[{\"name\": \"a\", \"path\": \"/a\", \"language\": \"Rust\", \"count\": 2}]";
+
+    #[test]
+    fn migrates_v0_and_backs_it_up() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("repo.json"), V0).unwrap();
+
+        let load = store(dir.path()).load().unwrap();
+        assert!(!load.needs_scan);
+        assert_eq!(load.repos.len(), 1);
+        assert_eq!(find(&load.repos, "/a").count, 2);
+        // the file is upgraded on the next save, the backup is the good file
+        assert_eq!(files(dir.path()), ["repo.json", "repo.json.bak"]);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("repo.json.bak")).unwrap(),
+            V0
+        );
+    }
+
+    #[test]
+    fn skips_invalid_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("repo.json"),
+            "{\"version\": 1, \"repos\": [{\"name\": \"b\"}, \
+             {\"name\": \"a\", \"path\": \"/a\", \"language\": \"Rust\", \"count\": 0}]}",
+        )
+        .unwrap();
+
+        let load = store(dir.path()).load().unwrap();
+        assert!(!load.needs_scan);
+        assert_eq!(load.repos.len(), 1);
+        assert_eq!(load.repos[0].path, "/a");
+    }
+
+    #[test]
+    fn quarantines_a_truncated_store_and_restores_the_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("repo.json");
+        store(dir.path())
+            .save(&[&test_repo("/a"), &test_repo("/b")])
+            .unwrap();
+        store(dir.path()).load().unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        fs::write(&path, &content[..content.len() / 2]).unwrap();
+
+        let load = store(dir.path()).load().unwrap();
+        // the backup may be behind, so a rescan follows
+        assert!(load.needs_scan);
+        assert_eq!(load.repos.len(), 2);
+        let files = files(dir.path());
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0], "repo.json.bak");
+        assert!(files[1].starts_with("repo.json.corrupt-"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join(&files[1])).unwrap(),
+            content[..content.len() / 2]
+        );
+    }
+
+    #[test]
+    fn a_truncated_store_without_backup_needs_a_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("repo.json"), &V0[..10]).unwrap();
+
+        let load = store(dir.path()).load().unwrap();
+        assert!(load.needs_scan);
+        assert!(load.repos.is_empty());
+        assert_eq!(files(dir.path()).len(), 1);
+        assert!(files(dir.path())[0].starts_with("repo.json.corrupt-"));
+    }
+
+    #[test]
+    fn newer_stores_are_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("repo.json");
+        let newer = "{\"version\": 99, \"repos\": [{\"name\": \"a\", \"path\": \"/a\", \
+                     \"language\": \"Rust\", \"count\": 0, \"pinned_by\": \"later\"}]}";
+        fs::write(&path, newer).unwrap();
+
+        let store = store(dir.path());
+        assert_eq!(store.load().unwrap().repos.len(), 1);
+        assert!(store.save(&[&test_repo("/b")]).is_err());
+        assert!(store.transact(&mut |repos| repos.clear()).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), newer);
+    }
+
+    #[test]
+    fn transactions_keep_changes_of_other_stores() {
+        let dir = tempfile::tempdir().unwrap();
+        // the launcher and the cli, each with its own snapshot of the index
+        let (gui, cli) = (store(dir.path()), store(dir.path()));
+        gui.save(&[&test_repo("/a"), &test_repo("/b")]).unwrap();
+        gui.load().unwrap();
+        cli.load().unwrap();
+
+        cli.transact(&mut |repos| {
+            repos.iter_mut().for_each(|repo| {
+                if repo.path == "/a" {
+                    repo.archived = true;
+                }
+            })
+        })
+        .unwrap();
+        let saved = gui
+            .transact(&mut |repos| {
+                repos.iter_mut().for_each(|repo| {
+                    if repo.path == "/b" {
+                        repo.count = 3;
+                    }
+                })
+            })
+            .unwrap();
+
+        assert!(find(&saved, "/a").archived);
+        let repos = store(dir.path()).load().unwrap().repos;
+        assert!(find(&repos, "/a").archived);
+        assert_eq!(find(&repos, "/b").count, 3);
+    }
+}
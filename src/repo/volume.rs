@@ -0,0 +1,231 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::Repo;
+
+/// known repos checked when looking for a volume at another mount path
+const MAX_PROBES: usize = 20;
+
+/// the configured base dir a repo was found under and where that base dir
+/// was mounted at the time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepoVolume {
+    pub base_dir: String,
+    pub mount: String,
+}
+
+/// a configured base dir and where it is mounted right now, `None` while
+/// the drive is missing or unmounted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Volume {
+    pub base_dir: String,
+    pub mount: Option<String>,
+}
+
+impl Volume {
+    pub fn is_online(&self) -> bool {
+        self.mount.is_some()
+    }
+}
+
+impl Repo {
+    /// the stored volume, or the configured base dir the path lies in for
+    /// repos indexed before volumes were tracked
+    pub fn volume_in(&self, base_dirs: &[String]) -> Option<RepoVolume> {
+        if let Some(volume) = &self.volume {
+            return Some(volume.clone());
+        }
+        base_dirs
+            .iter()
+            .find(|base_dir| Path::new(&self.path).starts_with(base_dir))
+            .map(|base_dir| RepoVolume {
+                base_dir: base_dir.clone(),
+                mount: base_dir.clone(),
+            })
+    }
+
+    /// move the repo to a new mount path of its volume
+    pub(crate) fn relocate(&mut self, mount: &str) {
+        let Some(volume) = self.volume.as_mut() else {
+            return;
+        };
+        let old = std::mem::replace(&mut volume.mount, mount.to_string());
+        self.path = rebase(&self.path, &old, mount);
+        self.parent = self.parent.as_deref().map(|path| rebase(path, &old, mount));
+    }
+}
+
+fn rebase(path: &str, from: &str, to: &str) -> String {
+    match Path::new(path).strip_prefix(from) {
+        Ok(rel) => Path::new(to).join(rel).to_string_lossy().to_string(),
+        Err(_) => path.to_string(),
+    }
+}
+
+/// an unmounted mount point is usually left behind as an empty directory
+fn is_online(path: &Path) -> bool {
+    fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_some())
+}
+
+/// find where every base dir is mounted. an offline base dir is looked for
+/// at its previous mount paths and next to them (e.g. `/Volumes/PSSD 1`),
+/// a candidate is accepted when most known repos show up at the same
+/// relative paths
+pub fn resolve_volumes(base_dirs: &[String], known: &HashSet<Repo>) -> Vec<Volume> {
+    base_dirs
+        .iter()
+        .map(|base_dir| {
+            let base = Path::new(base_dir);
+            if is_online(base) {
+                return Volume {
+                    base_dir: base_dir.clone(),
+                    mount: Some(base_dir.clone()),
+                };
+            }
+
+            let mut mounts = Vec::new();
+            let mut rels = Vec::new();
+            for repo in known.iter().filter(|repo| repo.parent.is_none()) {
+                let Some(volume) = repo.volume_in(base_dirs) else {
+                    continue;
+                };
+                if volume.base_dir != *base_dir {
+                    continue;
+                }
+                if let Ok(rel) = Path::new(&repo.path).strip_prefix(&volume.mount) {
+                    rels.push(rel.to_path_buf());
+                }
+                if !mounts.contains(&volume.mount) {
+                    mounts.push(volume.mount);
+                }
+            }
+            rels.sort();
+            rels.truncate(MAX_PROBES);
+
+            let mount = find_mount(base, &mounts, &rels, base_dirs);
+            Volume {
+                base_dir: base_dir.clone(),
+                mount: mount.map(|mount| mount.to_string_lossy().to_string()),
+            }
+        })
+        .collect()
+}
+
+fn find_mount(
+    base: &Path,
+    mounts: &[String],
+    rels: &[PathBuf],
+    base_dirs: &[String],
+) -> Option<PathBuf> {
+    if rels.is_empty() {
+        return None;
+    }
+
+    let mut candidates: Vec<PathBuf> = mounts.iter().map(PathBuf::from).collect();
+    let parents: HashSet<&Path> = std::iter::once(base)
+        .chain(mounts.iter().map(Path::new))
+        .filter_map(Path::parent)
+        .collect();
+    for parent in parents {
+        let Ok(entries) = fs::read_dir(parent) else {
+            continue;
+        };
+        candidates.extend(
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir()),
+        );
+    }
+
+    let needed = rels.len().div_ceil(2);
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate != base)
+        .filter(|candidate| !base_dirs.iter().any(|dir| Path::new(dir) == candidate))
+        .find(|candidate| {
+            rels.iter()
+                .filter(|rel| candidate.join(rel).join(".git").exists())
+                .count()
+                >= needed
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo::test_repo;
+
+    /// a drive at `<tmp>/Volumes/PSSD` holding the repos `one` and `two`
+    fn drive() -> (tempfile::TempDir, String, HashSet<Repo>) {
+        let root = tempfile::tempdir().unwrap();
+        let base = root.path().join("Volumes").join("PSSD");
+        let mut known = HashSet::new();
+        for name in ["one", "two"] {
+            fs::create_dir_all(base.join(name).join(".git")).unwrap();
+            known.insert(test_repo(&base.join(name).to_string_lossy()));
+        }
+        (root, base.to_string_lossy().to_string(), known)
+    }
+
+    fn mount(base: &str, known: &HashSet<Repo>) -> Option<String> {
+        let volumes = resolve_volumes(&[base.to_string()], known);
+        assert_eq!(volumes.len(), 1);
+        assert_eq!(volumes[0].base_dir, base);
+        volumes[0].mount.clone()
+    }
+
+    #[test]
+    fn a_mounted_base_dir_stays_where_it_is() {
+        let (_root, base, known) = drive();
+        assert_eq!(mount(&base, &known), Some(base));
+    }
+
+    #[test]
+    fn an_unplugged_drive_is_offline() {
+        let (_root, base, known) = drive();
+        // the empty mount point stays behind
+        fs::remove_dir_all(&base).unwrap();
+        fs::create_dir(&base).unwrap();
+        assert_eq!(mount(&base, &known), None);
+
+        fs::remove_dir(&base).unwrap();
+        assert_eq!(mount(&base, &known), None);
+    }
+
+    #[test]
+    fn a_drive_mounted_elsewhere_is_found_by_its_repos() {
+        let (root, base, known) = drive();
+        let moved = root.path().join("Volumes").join("PSSD 1");
+        fs::rename(&base, &moved).unwrap();
+        // another drive with unrelated repos is not mistaken for it
+        fs::create_dir_all(root.path().join("Volumes/Other/three/.git")).unwrap();
+
+        assert_eq!(
+            mount(&base, &known),
+            Some(moved.to_string_lossy().to_string())
+        );
+    }
+
+    #[test]
+    fn relocate_rebases_path_and_parent() {
+        let mut member = test_repo("/Volumes/PSSD/app/crates/core");
+        member.parent = Some("/Volumes/PSSD/app".to_string());
+        member.volume = member.volume_in(&["/Volumes/PSSD".to_string()]);
+
+        member.relocate("/Volumes/PSSD 1");
+        assert_eq!(member.path, "/Volumes/PSSD 1/app/crates/core");
+        assert_eq!(member.parent.as_deref(), Some("/Volumes/PSSD 1/app"));
+        assert_eq!(member.volume.as_ref().unwrap().mount, "/Volumes/PSSD 1");
+
+        // repos without a volume are left alone
+        let mut repo = test_repo("/elsewhere/app");
+        repo.relocate("/Volumes/PSSD 1");
+        assert_eq!(repo.path, "/elsewhere/app");
+    }
+}
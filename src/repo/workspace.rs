@@ -0,0 +1,480 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+/// a package or crate inside a monorepo
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkspaceMember {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// directories never descended into while expanding `**`
+const SKIP_DIRS: &[&str] = &[".git", "node_modules", "target", "dist", "build"];
+/// how deep `**` descends below the directory it starts in
+const MAX_GLOB_DEPTH: usize = 8;
+
+/// discover workspace members from cargo, npm/yarn/pnpm (also used by turbo),
+/// go.work and nx project lists
+pub fn discover_members(root: &Path) -> Vec<WorkspaceMember> {
+    let mut dirs = BTreeSet::new();
+    dirs.extend(cargo_members(root));
+    dirs.extend(package_json_members(root));
+    dirs.extend(pnpm_members(root));
+    dirs.extend(go_work_members(root));
+    dirs.extend(nx_members(root));
+
+    dirs.into_iter()
+        .filter(|dir| dir != root && dir.is_dir())
+        .map(|dir| WorkspaceMember {
+            name: member_name(&dir),
+            path: dir,
+        })
+        .collect()
+}
+
+/// package name from the member's manifest, falls back to the folder name
+fn member_name(dir: &Path) -> String {
+    let from_cargo = || {
+        let content = fs::read_to_string(dir.join("Cargo.toml")).ok()?;
+        let value: toml::Value = toml::from_str(&content).ok()?;
+        Some(value.get("package")?.get("name")?.as_str()?.to_string())
+    };
+    let from_package_json = || {
+        let content = fs::read_to_string(dir.join("package.json")).ok()?;
+        let value: Value = serde_json::from_str(&content).ok()?;
+        Some(value.get("name")?.as_str()?.to_string())
+    };
+    let from_go_mod = || {
+        let content = fs::read_to_string(dir.join("go.mod")).ok()?;
+        let module = content
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("module "))?;
+        Some(module.trim().rsplit('/').next()?.to_string())
+    };
+
+    from_cargo()
+        .or_else(from_package_json)
+        .or_else(from_go_mod)
+        .unwrap_or_else(|| {
+            dir.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        })
+}
+
+fn cargo_members(root: &Path) -> Vec<PathBuf> {
+    let Ok(content) = fs::read_to_string(root.join("Cargo.toml")) else {
+        return Vec::new();
+    };
+    let Ok(value) = toml::from_str::<toml::Value>(&content) else {
+        return Vec::new();
+    };
+    let Some(workspace) = value.get("workspace") else {
+        return Vec::new();
+    };
+
+    let patterns = |key: &str| -> Vec<String> {
+        workspace
+            .get(key)
+            .and_then(|list| list.as_array())
+            .map(|list| {
+                list.iter()
+                    .filter_map(|item| item.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    expand_patterns(root, &patterns("members"), &patterns("exclude"))
+}
+
+fn package_json_members(root: &Path) -> Vec<PathBuf> {
+    let Ok(content) = fs::read_to_string(root.join("package.json")) else {
+        return Vec::new();
+    };
+    let Ok(value) = serde_json::from_str::<Value>(&content) else {
+        return Vec::new();
+    };
+
+    // `"workspaces": [...]` or yarn's `"workspaces": { "packages": [...] }`
+    let list = match value.get("workspaces") {
+        Some(Value::Array(list)) => list,
+        Some(Value::Object(map)) => match map.get("packages") {
+            Some(Value::Array(list)) => list,
+            _ => return Vec::new(),
+        },
+        _ => return Vec::new(),
+    };
+
+    let (includes, excludes) = split_negations(list.iter().filter_map(Value::as_str));
+    expand_patterns(root, &includes, &excludes)
+}
+
+/// `pnpm-workspace.yaml`, only the `packages:` list is read
+fn pnpm_members(root: &Path) -> Vec<PathBuf> {
+    let Ok(content) = fs::read_to_string(root.join("pnpm-workspace.yaml")) else {
+        return Vec::new();
+    };
+
+    let mut in_packages = false;
+    let mut patterns = Vec::new();
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) && !trimmed.starts_with('-') {
+            in_packages = trimmed == "packages:";
+            continue;
+        }
+        if in_packages && let Some(item) = trimmed.strip_prefix('-') {
+            patterns.push(yaml_scalar(item));
+        }
+    }
+
+    let (includes, excludes) = split_negations(patterns.into_iter());
+    expand_patterns(root, &includes, &excludes)
+}
+
+/// a plain or quoted yaml scalar without its trailing comment
+fn yaml_scalar(item: &str) -> &str {
+    let item = item.trim();
+    for quote in ['\'', '"'] {
+        if let Some(rest) = item.strip_prefix(quote) {
+            return rest.split(quote).next().unwrap_or_default();
+        }
+    }
+    item.split(" #").next().unwrap_or_default().trim()
+}
+
+fn go_work_members(root: &Path) -> Vec<PathBuf> {
+    let Ok(content) = fs::read_to_string(root.join("go.work")) else {
+        return Vec::new();
+    };
+
+    let mut dirs = Vec::new();
+    let mut in_block = false;
+    for line in content.lines() {
+        let line = line.split("//").next().unwrap_or_default().trim();
+        if in_block {
+            if line == ")" {
+                in_block = false;
+            } else if !line.is_empty() {
+                dirs.extend(expand_glob(root, line.trim_matches('"')));
+            }
+        } else if let Some(rest) = line.strip_prefix("use") {
+            let rest = rest.trim();
+            if rest == "(" {
+                in_block = true;
+            } else if !rest.is_empty() {
+                dirs.extend(expand_glob(root, rest.trim_matches('"')));
+            }
+        }
+    }
+    dirs
+}
+
+/// nx projects from `workspace.json`, or `project.json` files in the usual
+/// apps/libs/packages folders
+fn nx_members(root: &Path) -> Vec<PathBuf> {
+    if let Ok(content) = fs::read_to_string(root.join("workspace.json"))
+        && let Ok(value) = serde_json::from_str::<Value>(&content)
+        && let Some(Value::Object(projects)) = value.get("projects")
+    {
+        return projects
+            .values()
+            .filter_map(|project| match project {
+                Value::String(path) => Some(path.as_str()),
+                Value::Object(map) => map.get("root")?.as_str(),
+                _ => None,
+            })
+            .flat_map(|path| expand_glob(root, path))
+            .collect();
+    }
+
+    if !root.join("nx.json").exists() {
+        return Vec::new();
+    }
+
+    expand_patterns(
+        root,
+        &[
+            "apps/*".to_string(),
+            "libs/*".to_string(),
+            "packages/*".to_string(),
+        ],
+        &[],
+    )
+    .into_iter()
+    .filter(|dir| dir.join("project.json").exists())
+    .collect()
+}
+
+fn split_negations<'a>(patterns: impl Iterator<Item = &'a str>) -> (Vec<String>, Vec<String>) {
+    let mut includes = Vec::new();
+    let mut excludes = Vec::new();
+    for pattern in patterns {
+        match pattern.strip_prefix('!') {
+            Some(exclude) => excludes.push(exclude.to_string()),
+            None => includes.push(pattern.to_string()),
+        }
+    }
+    (includes, excludes)
+}
+
+fn expand_patterns(root: &Path, includes: &[String], excludes: &[String]) -> Vec<PathBuf> {
+    let excluded: BTreeSet<PathBuf> = excludes
+        .iter()
+        .flat_map(|pattern| expand_glob(root, pattern))
+        .collect();
+
+    includes
+        .iter()
+        .flat_map(|pattern| expand_glob(root, pattern))
+        .filter(|dir| !excluded.contains(dir))
+        .collect()
+}
+
+/// expand a workspace glob relative to `root` into existing directories,
+/// supports `*` and `?` inside a path segment and `**` as a whole segment,
+/// paths leaving the root are ignored
+fn expand_glob(root: &Path, pattern: &str) -> Vec<PathBuf> {
+    let segments: Vec<&str> = pattern
+        .split('/')
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .collect();
+    if segments.contains(&"..") {
+        return Vec::new();
+    }
+
+    let mut matches = Vec::new();
+    expand_segments(root, &segments, 0, &mut matches);
+    matches
+}
+
+/// `depth` counts the directories `**` descended into. symlinked directories
+/// match a single segment but are never descended into by `**`, so a link
+/// back up the tree can't loop
+fn expand_segments(dir: &Path, segments: &[&str], depth: usize, matches: &mut Vec<PathBuf>) {
+    let Some((segment, rest)) = segments.split_first() else {
+        if dir.is_dir() {
+            matches.push(dir.to_path_buf());
+        }
+        return;
+    };
+
+    if !segment.contains(['*', '?']) {
+        expand_segments(&dir.join(segment), rest, depth, matches);
+        return;
+    }
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Ok(kind) = entry.file_type() else {
+            continue;
+        };
+        let is_dir = kind.is_dir() || (kind.is_symlink() && path.is_dir());
+        if !is_dir || SKIP_DIRS.contains(&name.as_str()) {
+            continue;
+        }
+
+        if *segment == "**" {
+            // `**` matches zero or more directories
+            if !kind.is_symlink() && depth < MAX_GLOB_DEPTH {
+                expand_segments(&path, segments, depth + 1, matches);
+            }
+        } else if wildcard_match(segment, &name) {
+            expand_segments(&path, rest, depth, matches);
+        }
+    }
+
+    if *segment == "**" {
+        expand_segments(dir, rest, depth, matches);
+    }
+}
+
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a root with the given files, every directory in `dirs` gets an empty
+    /// `.keep` so it exists
+    fn fixture(files: &[(&str, &str)], dirs: &[&str]) -> tempfile::TempDir {
+        let root = tempfile::tempdir().unwrap();
+        for dir in dirs {
+            fs::create_dir_all(root.path().join(dir)).unwrap();
+        }
+        for (path, content) in files {
+            let path = root.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        root
+    }
+
+    fn relative(root: &Path, dirs: Vec<PathBuf>) -> Vec<String> {
+        let mut dirs: Vec<String> = dirs
+            .iter()
+            .map(|dir| {
+                dir.strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+        dirs.sort();
+        dirs.dedup();
+        dirs
+    }
+
+    #[test]
+    fn parses_workspace_files() {
+        let dirs = [
+            "packages/a",
+            "packages/b",
+            "packages/legacy",
+            "apps/web/src",
+            "tools/lint",
+            "cmd/tool",
+            "lib",
+            "svc",
+            "quoted dir",
+        ];
+        let cases: &[(&str, &str, &str, &[&str])] = &[
+            (
+                "pnpm with comments, quotes and exclusions",
+                "pnpm-workspace.yaml",
+                concat!(
+                    "# the workspace\n",
+                    "packages:\n",
+                    "  - 'packages/*'\n",
+                    "  # the apps\n",
+                    "  - \"apps/*\" # web only\n",
+                    "  - tools/* # linters\n",
+                    "  - '!packages/legacy'\n",
+                    "catalog:\n",
+                    "  react: ^18\n",
+                ),
+                &["apps/web", "packages/a", "packages/b", "tools/lint"],
+            ),
+            (
+                "pnpm with a recursive glob",
+                "pnpm-workspace.yaml",
+                "packages:\n  - apps/**\n",
+                &["apps", "apps/web", "apps/web/src"],
+            ),
+            (
+                "go.work with a block, comments and quotes",
+                "go.work",
+                concat!(
+                    "go 1.22\n",
+                    "// tools live elsewhere\n",
+                    "use ./cmd/tool\n",
+                    "use (\n",
+                    "    ./lib // the library\n",
+                    "    \"./quoted dir\"\n",
+                    "    ../outside\n",
+                    ")\n",
+                ),
+                &["cmd/tool", "lib", "quoted dir"],
+            ),
+            (
+                "package.json workspaces with exclusions",
+                "package.json",
+                "{\"workspaces\": [\"packages/*\", \"!packages/legacy\"]}",
+                &["packages/a", "packages/b"],
+            ),
+            (
+                "yarn workspaces object",
+                "package.json",
+                "{\"workspaces\": {\"packages\": [\"apps/*\"]}}",
+                &["apps/web"],
+            ),
+            (
+                "cargo members and exclude",
+                "Cargo.toml",
+                "[workspace]\nmembers = [\"packages/*\"]\nexclude = [\"packages/b\"]\n",
+                &["packages/a", "packages/legacy"],
+            ),
+            (
+                "nx workspace.json",
+                "workspace.json",
+                "{\"projects\": {\"a\": \"packages/a\", \"web\": {\"root\": \"apps/web\"}}}",
+                &["apps/web", "packages/a"],
+            ),
+        ];
+
+        for (name, file, content, expected) in cases {
+            let root = fixture(&[(file, content)], &dirs);
+            let members = discover_members(root.path())
+                .into_iter()
+                .map(|member| member.path)
+                .collect();
+            assert_eq!(relative(root.path(), members), *expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn nx_projects_need_a_project_json() {
+        let root = fixture(
+            &[
+                ("nx.json", "{}"),
+                ("apps/web/project.json", "{}"),
+                ("libs/ui/project.json", "{}"),
+            ],
+            &["apps/docs"],
+        );
+        assert_eq!(
+            relative(root.path(), nx_members(root.path())),
+            ["apps/web", "libs/ui"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn recursive_globs_survive_symlink_loops() {
+        let root = fixture(&[], &["packages/a/nested"]);
+        std::os::unix::fs::symlink("..", root.path().join("packages/a/self")).unwrap();
+        std::os::unix::fs::symlink("/", root.path().join("packages/root")).unwrap();
+
+        let found = expand_glob(root.path(), "packages/**");
+        assert_eq!(
+            relative(root.path(), found),
+            ["packages", "packages/a", "packages/a/nested"]
+        );
+    }
+}
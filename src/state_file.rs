@@ -0,0 +1,90 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// advisory lock on `<path>.lock`, released on drop
+///
+/// the lock lives in a separate file because state files are replaced by
+/// rename, which would leave a lock on the data file behind on the old inode
+pub struct StateLock {
+    _file: File,
+}
+
+impl StateLock {
+    fn open(path: &Path) -> io::Result<File> {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".lock");
+        OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(PathBuf::from(name))
+    }
+
+    /// lock for writing, blocks while another process holds any lock
+    pub fn exclusive(path: &Path) -> io::Result<Self> {
+        let file = Self::open(path)?;
+        file.lock()?;
+        Ok(Self { _file: file })
+    }
+
+    /// lock for reading, blocks while another process is writing
+    pub fn shared(path: &Path) -> io::Result<Self> {
+        let file = Self::open(path)?;
+        file.lock_shared()?;
+        Ok(Self { _file: file })
+    }
+}
+
+/// read a state file under a shared lock
+pub fn read(path: &Path) -> io::Result<String> {
+    let _lock = StateLock::shared(path)?;
+    fs::read_to_string(path)
+}
+
+/// replace a state file atomically: write a temp file next to it, sync it and
+/// rename it over the original while holding the exclusive lock
+pub fn write(path: &Path, content: &[u8]) -> io::Result<()> {
+    let _lock = StateLock::exclusive(path)?;
+    replace(path, content)
+}
+
+/// `write` for callers that already hold the exclusive lock, e.g. to read,
+/// change and write a file as one step
+pub fn replace(path: &Path, content: &[u8]) -> io::Result<()> {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".tmp-{}", std::process::id()));
+    let tmp = PathBuf::from(name);
+
+    let result = (|| {
+        let mut file = File::create(&tmp)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// cheap fingerprint of a file used to notice changes made by other processes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl FileStamp {
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        })
+    }
+}
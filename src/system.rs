@@ -1,15 +1,13 @@
 use std::io;
 use std::path::Path;
-use std::process::Command;
+use std::process::{Child, Command};
 
 pub struct FileOpener;
 
 impl FileOpener {
-    /// 使用指定程序打开文件
-    pub fn open_with(program: &str, path: &str) -> io::Result<()> {
-        let path_obj = Path::new(path);
-
-        if !path_obj.exists() {
+    /// 使用指定程序同时打开多个文件
+    pub fn open_all_with(program: &str, paths: &[&str]) -> io::Result<()> {
+        if let Some(path) = paths.iter().find(|path| !Path::new(path).exists()) {
             return Err(io::Error::new(
                 io::ErrorKind::NotFound,
                 format!("文件不存在: {}", path),
@@ -20,7 +18,7 @@ impl FileOpener {
         let status = Command::new("open")
             .arg("-a")
             .arg(program)
-            .arg(path)
+            .args(paths)
             .status()?;
 
         if !status.success() {
@@ -33,3 +31,16 @@ impl FileOpener {
         Ok(())
     }
 }
+
+pub struct ActionRunner;
+
+impl ActionRunner {
+    /// 在仓库目录中通过 shell 执行自定义命令
+    pub fn run(command: &str, dir: &str) -> io::Result<Child> {
+        Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .current_dir(dir)
+            .spawn()
+    }
+}